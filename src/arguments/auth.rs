@@ -0,0 +1,283 @@
+// Module adds the Authorization header to each request. Supported schemes:
+//
+//   basic:<USER>:<PASS>     HTTP basic authentication
+//   bearer:<TOKEN>          a bearer token, e.g. a JWT
+//   digest:<USER>:<PASS>    HTTP digest authentication (MD5, SHA-256 and their -sess variants)
+//
+// Digest challenges are shared by every worker so only the first request, or a request answered with a
+// stale nonce, has to be sent twice.
+use std::sync::{
+  Arc, RwLock,
+  atomic::{AtomicU32, Ordering},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use md5::Md5;
+use sha2::{Sha256, Digest};
+use reqwest::{Url, StatusCode, header::{HeaderMap, WWW_AUTHENTICATE}};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestChallenge {
+  pub realm: String,
+  pub nonce: String,
+  pub qop: Option<String>,
+  pub opaque: Option<String>,
+  pub algorithm: String,
+}
+
+#[derive(Debug)]
+pub struct DigestAuth {
+  pub user: String,
+  pub pass: String,
+  pub challenge: RwLock<Option<DigestChallenge>>,   // The last challenge sent by the server.
+  pub count: AtomicU32,                             // The nonce count, reset with every new challenge.
+}
+
+#[derive(Debug, Clone)]
+pub enum Auth {
+  None,
+  Basic { user: String, pass: String },
+  Bearer(String),
+  Digest(Arc<DigestAuth>),
+}
+
+/**Function splits the parameters of a WWW-Authenticate header, e.g. realm="a", nonce="b", algorithm=MD5
+ * Params:
+ *  params: &str {The header without the scheme.}
+ * Returns Vec<(String, String)> {The lowercase names and unquoted values.}
+ */
+pub fn parse_params(params: &str) -> Vec<(String, String)> {
+  let mut out: Vec<(String, String)> = Default::default();
+  let mut chars = params.chars().peekable();
+
+  loop {
+    while let Some(c) = chars.peek() {
+      if *c == ',' || c.is_whitespace() { chars.next(); } else { break; }
+    }
+
+    let mut name = String::new();
+    for c in chars.by_ref() {
+      if c == '=' { break; }
+      name.push(c);
+    }
+
+    if name.trim().is_empty() {
+      break;
+    }
+
+    let mut value = String::new();
+    if chars.peek() == Some(&'"') {
+      chars.next();
+      while let Some(c) = chars.next() {
+        match c {
+          '\\' => { if let Some(n) = chars.next() { value.push(n); } },
+          '"' => { break; },
+          _ => { value.push(c); }
+        }
+      }
+    }
+    else {
+      while let Some(c) = chars.peek() {
+        if *c == ',' { break; }
+        value.push(*c);
+        chars.next();
+      }
+    }
+
+    out.push((name.trim().to_lowercase(), value.trim().to_owned()));
+  }
+
+  out
+}
+
+impl DigestChallenge {
+
+  /**Function parses a digest challenge from a WWW-Authenticate header.
+   * Params:
+   *  header: &str {The header value.}
+   * Returns Option<DigestChallenge> {None when the header is not a digest challenge.}
+   */
+  pub fn parse(header: &str) -> Option<DigestChallenge> {
+    let (scheme, params) = header.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("Digest") {
+      return None;
+    }
+
+    let mut challenge = DigestChallenge {
+      realm: String::new(), nonce: String::new(), qop: None, opaque: None, algorithm: "MD5".to_owned()
+    };
+
+    for (name, value) in parse_params(params) {
+      match name.as_str() {
+        "realm" => { challenge.realm = value; },
+        "nonce" => { challenge.nonce = value; },
+        "qop" => { challenge.qop = Some(value); },
+        "opaque" => { challenge.opaque = Some(value); },
+        "algorithm" => { challenge.algorithm = value; },
+        _ => {}
+      }
+    }
+
+    match challenge.nonce.len() {
+      0 => None,
+      _ => Some(challenge),
+    }
+  }
+}
+
+impl DigestAuth {
+
+  fn hash(algorithm: &str, input: &str) -> String {
+    let digest: Vec<u8> = match algorithm.to_uppercase().starts_with("SHA-256") {
+      true => Sha256::digest(input.as_bytes()).to_vec(),
+      false => Md5::digest(input.as_bytes()).to_vec(),
+    };
+
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+  }
+
+  /**Function stores a new challenge from the server.
+   * Params:
+   *  &self
+   *  header: &str {The WWW-Authenticate header.}
+   * Returns bool {True when the header contained a digest challenge.}
+   */
+  pub fn update(&self, header: &str) -> bool {
+    let challenge = match DigestChallenge::parse(header) {
+      Some(c) => c,
+      None => { return false; }
+    };
+
+    match self.challenge.write() {
+      Ok(mut c) => {
+        *c = Some(challenge);
+        self.count.store(0, Ordering::SeqCst);
+        true
+      },
+      Err(_) => { false }
+    }
+  }
+
+  /**Function builds the Authorization header for a request using the last challenge.
+   * Params:
+   *  &self
+   *  method: &str {The request method.}
+   *  url:    &str {The request url.}
+   * Returns Option<String> {None until the server has sent a challenge.}
+   */
+  pub fn header(&self, method: &str, url: &str) -> Option<String> {
+    let challenge = match self.challenge.read() {
+      Ok(c) => c.clone()?,
+      Err(_) => { return None; }
+    };
+
+    let uri = match Url::parse(url) {
+      Ok(u) => {
+        match u.query() {
+          Some(q) => format!("{}?{}", u.path(), q),
+          None => u.path().to_owned(),
+        }
+      },
+      Err(_) => url.to_owned(),
+    };
+
+    let algorithm = challenge.algorithm.as_str();
+    let cnonce = Uuid::new_v4().simple().to_string();
+    let nc = format!("{:08x}", self.count.fetch_add(1, Ordering::SeqCst) + 1);
+
+    let mut ha1 = Self::hash(algorithm, format!("{}:{}:{}", self.user, challenge.realm, self.pass).as_str());
+    if algorithm.to_lowercase().ends_with("-sess") {
+      ha1 = Self::hash(algorithm, format!("{}:{}:{}", ha1, challenge.nonce, cnonce).as_str());
+    }
+
+    let ha2 = Self::hash(algorithm, format!("{}:{}", method, uri).as_str());
+    let qop = challenge.qop.as_deref().filter(|q| q.split(',').any(|i| i.trim() == "auth")).map(|_| "auth");
+
+    let response = match qop {
+      Some(q) => Self::hash(algorithm, format!("{}:{}:{}:{}:{}:{}", ha1, challenge.nonce, nc, cnonce, q, ha2).as_str()),
+      None => Self::hash(algorithm, format!("{}:{}:{}", ha1, challenge.nonce, ha2).as_str()),
+    };
+
+    let mut header = format!(
+      "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+      self.user, challenge.realm, challenge.nonce, uri, algorithm, response
+    );
+
+    if let Some(q) = qop {
+      header.push_str(format!(", qop={}, nc={}, cnonce=\"{}\"", q, nc, cnonce).as_str());
+    }
+
+    if let Some(o) = challenge.opaque {
+      header.push_str(format!(", opaque=\"{}\"", o).as_str());
+    }
+
+    Some(header)
+  }
+}
+
+impl Auth {
+
+  /**Function parses the --auth option.
+   * Params:
+   *  spec: &str {The scheme and credentials, e.g. basic:admin:admin or bearer:eyJ...}
+   * Returns Result<Auth, String>
+   */
+  pub fn parse(spec: &str) -> Result<Auth, String> {
+    let (scheme, value) = match spec.split_once(':') {
+      Some(s) => s,
+      None => { return Err(format!("{} expects basic:<USER>:<PASS>, digest:<USER>:<PASS> or bearer:<TOKEN>", spec)); }
+    };
+
+    match scheme.to_lowercase().as_str() {
+      "basic" | "digest" => {
+        let (user, pass) = match value.split_once(':') {
+          Some(s) => s,
+          None => { return Err(format!("{} authentication expects <USER>:<PASS>", scheme)); }
+        };
+
+        match scheme.to_lowercase().as_str() {
+          "basic" => Ok(Auth::Basic { user: user.to_owned(), pass: pass.to_owned() }),
+          _ => Ok(Auth::Digest(Arc::new(DigestAuth {
+            user: user.to_owned(), pass: pass.to_owned(), challenge: RwLock::new(None), count: AtomicU32::new(0)
+          }))),
+        }
+      },
+      "bearer" if !value.is_empty() => { Ok(Auth::Bearer(value.to_owned())) },
+      "bearer" => { Err("bearer authentication expects a token".to_owned()) },
+      _ => { Err(format!("unknown authentication scheme {}", scheme)) }
+    }
+  }
+
+  /**Function returns the Authorization header for a request.
+   * Params:
+   *  &self
+   *  method: &str {The request method.}
+   *  url:    &str {The request url.}
+   * Returns Option<String>
+   */
+  pub fn header(&self, method: &str, url: &str) -> Option<String> {
+    match self {
+      Auth::None => None,
+      Auth::Basic { user, pass } => Some(format!("Basic {}", BASE64.encode(format!("{}:{}", user, pass)))),
+      Auth::Bearer(token) => Some(format!("Bearer {}", token)),
+      Auth::Digest(digest) => digest.header(method, url),
+    }
+  }
+
+  /**Function stores the digest challenge from a 401 response.
+   * Params:
+   *  &self
+   *  status:  StatusCode  {The status code of the response.}
+   *  headers: &HeaderMap  {The response headers.}
+   * Returns bool {True when the request should be sent again to answer the new challenge.}
+   */
+  pub fn new_challenge(&self, status: StatusCode, headers: &HeaderMap) -> bool {
+    match (self, status) {
+      (Auth::Digest(digest), StatusCode::UNAUTHORIZED) => {
+        headers.get_all(WWW_AUTHENTICATE).iter().filter_map(|h| h.to_str().ok()).any(|h| digest.update(h))
+      },
+      _ => false,
+    }
+  }
+}
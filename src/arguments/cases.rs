@@ -0,0 +1,151 @@
+// Module turns the words read from the wordlists into test cases. The cases are made on their own thread
+// and handed to the workers through a bounded channel, so the first request is sent before the wordlists
+// have been read to the end and memory use does not grow with the size of the wordlists.
+use std::{
+  collections::HashSet,
+  sync::{Arc, mpsc::SyncSender},
+  sync::atomic::Ordering,
+  thread,
+};
+
+use super::{Fuzz, FuzzControl, TestCase, WordlistReader, WordlistSource, NormalizeOptions};
+use super::normalizer::DropStats;
+use super::encoders::{self, Encoder};
+use super::rules::{self, Rule};
+use super::extensions::ExtensionPlan;
+use super::targets::Target;
+use super::request;
+
+pub const QUEUE_SIZE: usize = 4096;       // The number of test cases waiting between the wordlist reader and the workers.
+
+#[derive(Debug, Clone)]
+pub struct CaseFactory {
+  pub fuzz: Fuzz,
+  pub encoders: Vec<Encoder>,
+  pub rules: Vec<Rule>,
+  pub targets: Vec<Target>,
+  pub ext_plan: ExtensionPlan,
+  pub dedup: bool,              // Drops variants that were already made from another word.
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CaseStats {
+  pub words_read: usize,
+  pub variants: usize,          // The number of words after the rules were applied.
+  pub dropped: DropStats,       // The wordlist entries that were dropped by the reader.
+}
+
+impl CaseFactory {
+
+  /**Function returns the name shown with each result, only when there is more than one target. */
+  fn tag(&self, target: &Target) -> Option<Arc<str>> {
+    match self.targets.len() {
+      1 => None,
+      _ => Some(target.name.clone()),
+    }
+  }
+
+  /**Function turns one payload into a test case for each target in turn.
+   * Params:
+   *  &self
+   *  chunk: &str   {The encoded payload.}
+   *  word:  usize  {The position of the word the payload was made from.}
+   * Returns Vec<TestCase>
+   */
+  pub fn payload_cases(&self, chunk: &str, word: usize) -> Vec<TestCase> {
+    let mut cases: Vec<TestCase> = Default::default();
+
+    if self.fuzz == Fuzz::DirectoryPath {
+
+      // "http://url + word + '.' + "ext", then the bare word and backup files.
+      for name in self.ext_plan.expand(chunk) {
+        for target in self.targets.iter() {
+          let url = target.url.as_str();
+          let case_url = match url.contains(request::FUZZ_MARKER) {
+            true => url.replace(request::FUZZ_MARKER, name.as_str()),             // The path comes from a template.
+            false => format!("{url}{name}"),
+          };
+
+          cases.push(TestCase { url: case_url, payload: name.clone(), target: self.tag(target), word });
+        }
+      }
+    }
+    else if self.fuzz == Fuzz::Parameter {
+      for target in self.targets.iter() {
+        let case_url = target.url.replace(request::FUZZ_MARKER, chunk);
+        cases.push(TestCase { url: case_url, payload: chunk.to_owned(), target: self.tag(target), word });
+      }
+    }
+
+    cases
+  }
+
+  /**Function reads every word, expands it with the rules and sends each test case to the workers. It stops
+   * early when the scan is quit or the queue has been closed.
+   * Params:
+   *  &self
+   *  reader:  WordlistReader        {Streams the normalized words.}
+   *  sender:  SyncSender<TestCase>  {The bounded queue read by the workers.}
+   *  control: &FuzzControl          {Counts the test cases that have been made.}
+   * Returns CaseStats.
+   */
+  pub fn produce(&self, mut reader: WordlistReader, sender: SyncSender<TestCase>, control: &FuzzControl) -> CaseStats {
+    let mut stats = CaseStats::default();
+    let mut variants_seen: HashSet<String> = Default::default();
+
+    'words: for word in reader.by_ref() {
+      let position = stats.words_read;
+      stats.words_read += 1;
+
+      let variants = match self.rules.len() {
+        0 => vec![word],
+        _ => rules::expand(&self.rules, word.as_str())
+      };
+
+      for variant in variants {
+        // Different words can expand into the same variant.
+        if !self.rules.is_empty() && self.dedup && !variants_seen.insert(variant.clone()) {
+          continue;
+        }
+
+        stats.variants += 1;
+        let payload = encoders::apply_chain(&self.encoders, variant.as_str());
+
+        for case in self.payload_cases(payload.as_str(), position) {
+          control.total.fetch_add(1, Ordering::SeqCst);
+
+          if control.should_quit() || sender.send(case).is_err() {
+            break 'words;
+          }
+        }
+      }
+    }
+
+    stats.dropped = reader.stats;
+    stats
+  }
+}
+
+/**Function starts the thread that reads the wordlists and fills the queue. The queue is closed when the
+ * thread returns, which tells the workers that there are no test cases left.
+ * Params:
+ *  factory: CaseFactory           {Turns words into test cases.}
+ *  sources: Vec<WordlistSource>   {The wordlists to be read in order.}
+ *  options: NormalizeOptions      {Controls trimming, comments and duplicate words.}
+ *  debug:   bool                  {Displays errors for sources that cannot be opened.}
+ *  sender:  SyncSender<TestCase>  {The bounded queue read by the workers.}
+ *  control: Arc<FuzzControl>      {The state shared with the workers.}
+ * Returns JoinHandle<CaseStats>
+ */
+pub fn start_producer(
+  factory: CaseFactory, sources: Vec<WordlistSource>, options: NormalizeOptions, debug: bool, sender: SyncSender<TestCase>, control: Arc<FuzzControl>
+) -> thread::JoinHandle<CaseStats> {
+  thread::spawn(move || {
+    let reader = WordlistReader::new(sources, options, debug);
+    let stats = factory.produce(reader, sender, &control);
+
+    control.generating.store(false, Ordering::SeqCst);
+    control.stdin_wordlist.store(false, Ordering::SeqCst);
+    stats
+  })
+}
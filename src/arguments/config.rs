@@ -0,0 +1,192 @@
+// Module loads default options from TOML files so long command lines do not have to be repeated. Keys
+// are the long option names and profiles override the top level values:
+//
+//   threads = 20
+//   scodes = "200,301,403"
+//   header = ["X-Api-Key: abc", "Accept: */*"]
+//
+//   [profile.stealth]
+//   threads = 1
+//   timeout = 5000
+//
+// ~/.config/outrageous_soda/config.toml is read first, then --config, and options given on the command
+// line take precedence over both.
+use std::{fs, path::PathBuf};
+
+use clap::{Arg, ArgMatches, Command, ValueSource};
+use toml::{Table, Value};
+
+pub const PROFILE_KEY: &str = "profile";
+const CONFIG_DIR: &str = ".config/outrageous_soda";
+const CONFIG_FILE: &str = "config.toml";
+
+// Options that select the configuration and cannot be set by it.
+const RESERVED_KEYS: [&str; 2] = ["config", "profile"];
+
+/**Function returns the path of the config file in the home directory. */
+pub fn default_config_path() -> Option<PathBuf> {
+  let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+  Some(PathBuf::from(home).join(CONFIG_DIR).join(CONFIG_FILE))
+}
+
+/**Function reads a TOML config file.
+ * Params:
+ *  file_name: &str {The path to the file.}
+ * Returns Result<Table, String>
+ */
+pub fn load_table(file_name: &str) -> Result<Table, String> {
+  let text = match fs::read_to_string(file_name) {
+    Ok(t) => t,
+    Err(e) => { return Err(format!("unable to read config file {} - {}", file_name, e)); }
+  };
+
+  match text.parse::<Table>() {
+    Ok(t) => Ok(t),
+    Err(e) => Err(format!("invalid config file {} - {}", file_name, e)),
+  }
+}
+
+/**Function merges a config file into the values loaded so far. Profiles with the same name are merged
+ * key by key so a project file can change part of a profile from the home directory.
+ * Params:
+ *  base:  &mut Table {The values loaded so far.}
+ *  other: Table      {The values that take precedence.}
+ * Returns nothing.
+ */
+pub fn merge_tables(base: &mut Table, other: Table) {
+  for (key, value) in other {
+    match (base.get_mut(key.as_str()), value) {
+      (Some(Value::Table(profiles)), Value::Table(new_profiles)) if key == PROFILE_KEY => {
+        for (name, profile) in new_profiles {
+          match (profiles.get_mut(name.as_str()), profile) {
+            (Some(Value::Table(old)), Value::Table(new)) => { old.extend(new); },
+            (_, profile) => { profiles.insert(name, profile); }
+          }
+        }
+      },
+      (_, value) => { base.insert(key, value); }
+    }
+  }
+}
+
+/**Function returns the options of the selected profile on top of the top level options.
+ * Params:
+ *  table:   &Table        {The merged config files.}
+ *  profile: Option<&str>  {The profile from --profile.}
+ * Returns Result<Table, String>
+ */
+pub fn select_profile(table: &Table, profile: Option<&str>) -> Result<Table, String> {
+  let mut options: Table = table.iter()
+    .filter(|(k, _)| k.as_str() != PROFILE_KEY)
+    .map(|(k, v)| (k.clone(), v.clone()))
+    .collect();
+
+  let name = match profile {
+    Some(n) => n,
+    None => { return Ok(options); }
+  };
+
+  let profiles = match table.get(PROFILE_KEY) {
+    Some(Value::Table(p)) => p,
+    Some(_) => { return Err("profile must be a table of profiles, e.g. [profile.stealth]".to_owned()); },
+    None => { return Err(format!("profile {} was not found, no profiles are configured", name)); }
+  };
+
+  match profiles.get(name) {
+    Some(Value::Table(p)) => { options.extend(p.clone()); },
+    Some(_) => { return Err(format!("profile {} must be a table, e.g. [profile.{}]", name, name)); },
+    None => {
+      let names: Vec<&str> = profiles.keys().map(|k| k.as_str()).collect();
+      return Err(format!("profile {} was not found, available profiles: {}", name, names.join(", ")));
+    }
+  }
+
+  Ok(options)
+}
+
+/**Function converts a config value into the text given on the command line. Lists are joined with
+ * commas like the list options.
+ */
+fn value_text(value: &Value) -> Result<String, String> {
+  match value {
+    Value::String(s) => Ok(s.clone()),
+    Value::Integer(i) => Ok(i.to_string()),
+    Value::Float(f) => Ok(f.to_string()),
+    Value::Boolean(b) => Ok(b.to_string()),
+    Value::Array(a) => {
+      let items: Result<Vec<String>, String> = a.iter().map(value_text).collect();
+      Ok(items?.join(","))
+    },
+    Value::Datetime(d) => Ok(d.to_string()),
+    Value::Table(_) => Err("tables can only be used for profiles".to_owned()),
+  }
+}
+
+/**Function checks if an option was given on the command line. */
+fn given(matches: &ArgMatches, arg: &Arg) -> bool {
+  matches.value_source(arg.get_id()) == Some(ValueSource::CommandLine)
+}
+
+/**Function converts the config options into command line arguments, leaving out the options given on
+ * the command line and options that conflict with them.
+ * Params:
+ *  command: &Command     {The command line definition.}
+ *  matches: &ArgMatches  {The options given on the command line.}
+ *  options: &Table       {The options from the config files.}
+ * Returns Result<Vec<String>, String>
+ */
+pub fn config_args(command: &Command, matches: &ArgMatches, options: &Table) -> Result<Vec<String>, String> {
+  let mut args: Vec<String> = Default::default();
+
+  for (key, value) in options {
+    let long = key.replace('_', "-");
+
+    if RESERVED_KEYS.contains(&long.as_str()) {
+      return Err(format!("{} can only be set on the command line", long));
+    }
+
+    let arg = match command.get_arguments().find(|a| a.get_long() == Some(long.as_str()) || a.get_id() == long) {
+      Some(a) if a.is_positional() => {
+        return Err(format!("{} is an argument and must be given on the command line", key));
+      },
+      Some(a) => a,
+      None => { return Err(format!("unknown option {} in config", key)); }
+    };
+
+    let long = match arg.get_long() {
+      Some(l) => l,
+      None => { return Err(format!("{} does not have a long name and cannot be configured", key)); }
+    };
+
+    if given(matches, arg) {
+      continue;
+    }
+
+    let conflicts = command.get_arg_conflicts_with(arg).iter().any(|c| given(matches, c)) ||
+      command.get_arguments().any(|a| given(matches, a) && command.get_arg_conflicts_with(a).iter().any(|c| c.get_id() == arg.get_id()));
+
+    if conflicts {
+      continue;
+    }
+
+    match value {
+      Value::Boolean(false) => {},
+      Value::Array(items) if arg.is_multiple_occurrences_set() => {
+        for item in items {
+          match value_text(item) {
+            Ok(v) => { args.push(format!("--{}={}", long, v)); },
+            Err(e) => { return Err(format!("{}: {}", key, e)); }
+          }
+        }
+      },
+      _ => {
+        match value_text(value) {
+          Ok(v) => { args.push(format!("--{}={}", long, v)); },
+          Err(e) => { return Err(format!("{}: {}", key, e)); }
+        }
+      }
+    }
+  }
+
+  Ok(args)
+}
@@ -0,0 +1,422 @@
+// Module holds the state shared between the main thread, the worker threads and the Ctrl-C handler.
+use std::{
+  io::{self, Write, BufWriter, Error as IoError, ErrorKind},
+  fs::{self, OpenOptions},
+  sync::{Mutex, RwLock},
+  sync::mpsc::{self, Sender, Receiver, RecvTimeoutError},
+  sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+  thread,
+  time::{Duration, Instant},
+};
+
+use console::style;
+use reqwest::{StatusCode, Version};
+
+use super::output::OutputEvent;
+use super::request::TestCase;
+use super::normalizer::NormalizeOptions;
+use super::wordlist::{WordlistReader, WordlistSource};
+
+pub const DEFAULT_DELAY: u64 = 100;                          // The default delay in miliseconds between each request of a worker thread.
+pub const DEFAULT_CHECKPOINT: &str = "soda_checkpoint.txt";  // The default file name for saved checkpoints.
+
+#[derive(Debug)]
+pub struct FuzzControl {
+  pub paused: AtomicBool,                   // Workers stop taking test cases while this is set.
+  pub quit: AtomicBool,                     // Workers exit and the output file is flushed when this is set.
+  pub delay: AtomicU64,                     // The delay in miliseconds each worker sleeps between requests.
+  pub active_threads: AtomicUsize,          // Workers with an id greater or equal to this value stay parked.
+  pub running: AtomicUsize,                 // The number of workers that have not finished yet.
+  pub completed: AtomicUsize,               // The number of test cases that have been sent.
+  pub total: AtomicUsize,                   // The number of test cases made so far.
+  pub current_word: AtomicUsize,            // The position of the word of the last test case taken, where a checkpoint resumes.
+  pub generating: AtomicBool,               // Set while the wordlists are still being read.
+  pub stdin_wordlist: AtomicBool,           // Set while the wordlist is read from stdin, which the pause menu cannot share.
+  pub status_codes: RwLock<Vec<StatusCode>>,
+  pub ignore_len: RwLock<Vec<u64>>,
+  queue: Mutex<Option<Receiver<TestCase>>>,   // None once the queue has been closed.
+  wordlists: Vec<WordlistSource>,           // Read again from the start when a checkpoint is saved.
+  options: NormalizeOptions,
+  output: Sender<OutputEvent>,              // Every result is sent to the output thread through this channel.
+}
+
+impl FuzzControl {
+
+  /**Function creates the shared control structure around the queue of test cases.
+   * Params:
+   *  cases:        Receiver<TestCase>  {The requests waiting to be sent, filled while the wordlists are read.}
+   *  wordlists:    Vec<WordlistSource>  {The wordlists the test cases are made from.}
+   *  options:      NormalizeOptions  {How the words were normalized.}
+   *  threads:      usize             {The number of worker threads.}
+   *  status_codes: Vec<StatusCode>   {The status codes to be displayed.}
+   *  ignore_len:   Vec<u64>          {The response lengths to be hidden.}
+   *  output:       Sender<OutputEvent>  {The channel to the output thread.}
+   * Returns FuzzControl.
+   */
+  pub fn new(
+    cases: Receiver<TestCase>, wordlists: Vec<WordlistSource>, options: NormalizeOptions, threads: usize,
+    status_codes: Vec<StatusCode>, ignore_len: Vec<u64>, output: Sender<OutputEvent>
+  ) -> FuzzControl {
+    FuzzControl {
+      paused: AtomicBool::new(false),
+      quit: AtomicBool::new(false),
+      delay: AtomicU64::new(match threads { 0 => 0, _ => DEFAULT_DELAY }),   // The main thread sends without a delay.
+      active_threads: AtomicUsize::new(threads.max(1)),       // The main thread sends the requests when there are no workers.
+      running: AtomicUsize::new(0),
+      completed: AtomicUsize::new(0),
+      total: AtomicUsize::new(0),
+      current_word: AtomicUsize::new(0),
+      generating: AtomicBool::new(true),
+      stdin_wordlist: AtomicBool::new(false),
+      status_codes: RwLock::new(status_codes),
+      ignore_len: RwLock::new(ignore_len),
+      queue: Mutex::new(Some(cases)),
+      wordlists,
+      options,
+      output,
+    }
+  }
+
+  /**Function sends an event to the output thread.
+   * Params:
+   *  &self
+   *  event: OutputEvent {The event to be displayed or logged.}
+   * Returns nothing.
+   */
+  pub fn emit(&self, event: OutputEvent) {
+    let _ = self.output.send(event);
+  }
+
+  /**Function asks the output thread to flush the output file and waits until it is done.
+   * Params:
+   *  &self
+   *  wait: Duration {How long to wait for the output thread.}
+   * Returns bool.
+   */
+  pub fn shutdown_output(&self, wait: Duration) -> bool {
+    let (ack, done) = mpsc::channel::<()>();
+    self.emit(OutputEvent::Shutdown(ack));
+
+    done.recv_timeout(wait).is_ok()
+  }
+
+  /**Function takes the next test case off the queue, waiting while the wordlists are still being read.
+   * Params:
+   *  &self
+   * Returns Option<TestCase> {None when every test case has been taken or the scan was quit.}
+   */
+  pub fn next_case(&self) -> Option<TestCase> {
+    loop {
+      // The lock is given up between tries so a checkpoint can be saved while a worker waits.
+      let next = match self.queue.lock() {
+        Ok(queue) => {
+          match queue.as_ref() {
+            Some(q) => { q.recv_timeout(Duration::from_millis(50)) },
+            None => { return None; }
+          }
+        },
+        Err(_) => { return None; }
+      };
+
+      match next {
+        Ok(case) => {
+          self.current_word.store(case.word, Ordering::SeqCst);
+          return Some(case);
+        },
+        Err(RecvTimeoutError::Disconnected) => { return None; },
+        Err(RecvTimeoutError::Timeout) => {
+          if self.should_quit() {
+            return None;
+          }
+        }
+      }
+    }
+  }
+
+  /**Function closes the queue so the wordlist reader stops making test cases.
+   * Params:
+   *  &self
+   * Returns nothing.
+   */
+  pub fn close_queue(&self) {
+    if let Ok(mut queue) = self.queue.lock() {
+      *queue = None;
+    }
+  }
+
+  /**Function returns the number of test cases that have been made but not sent yet.
+   * Params:
+   *  &self
+   * Returns usize.
+   */
+  pub fn pending(&self) -> usize {
+    self.total.load(Ordering::SeqCst).saturating_sub(self.completed.load(Ordering::SeqCst))
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused.load(Ordering::SeqCst)
+  }
+
+  pub fn should_quit(&self) -> bool {
+    self.quit.load(Ordering::SeqCst)
+  }
+
+  /**Function blocks a worker while the scan is paused or the worker has been parked.
+   * Params:
+   *  &self
+   *  id: usize {The id of the worker.}
+   * Returns bool {false when the worker should exit.}
+   */
+  pub fn wait_turn(&self, id: usize) -> bool {
+    while self.is_paused() || id >= self.active_threads.load(Ordering::SeqCst) {
+      if self.should_quit() {
+        return false;
+      }
+
+      thread::sleep(Duration::from_millis(50));
+    }
+
+    !self.should_quit()
+  }
+
+  /**Function checks if a response should be displayed based on the status code and length filters.
+   * Params:
+   *  &self
+   *  status: StatusCode  {The status code of the response.}
+   *  len:    u64         {The length of the response.}
+   * Returns bool.
+   */
+  pub fn is_match(&self, status: StatusCode, len: u64) -> bool {
+    let code_match = match self.status_codes.read() {
+      Ok(codes) => { codes.contains(&status) },
+      Err(_) => { false }
+    };
+
+    let len_match = match self.ignore_len.read() {
+      Ok(lens) => { lens.contains(&len) },
+      Err(_) => { false }
+    };
+
+    code_match && !len_match
+  }
+
+  /**Function passes a response to the output thread, as a hit when it matches the filters and as a
+   * response in verbose mode.
+   * Params:
+   *  &self
+   *  request:  &str            {The label of the test case.}
+   *  status:   StatusCode      {The status code of the response.}
+   *  len:      u64             {The length of the response.}
+   *  location: Option<String>  {Where a redirect points to.}
+   *  version:  Option<Version> {The HTTP version of the response, shown when a protocol was selected.}
+   *  verbose:  bool            {Shows every response.}
+   *  debug:    bool            {Hits are not shown in debug mode.}
+   * Returns bool {True when the response is a hit.}
+   */
+  #[allow(clippy::too_many_arguments)]
+  pub fn report(&self, request: &str, status: StatusCode, len: u64, location: Option<String>, version: Option<Version>, verbose: bool, debug: bool) -> bool {
+    let hit = !debug && self.is_match(status, len);
+
+    if hit {
+      self.emit(OutputEvent::Hit { request: request.to_owned(), status, len, location: location.clone(), version });
+    }
+
+    if verbose {
+      self.emit(OutputEvent::Response { request: request.to_owned(), status, len, location, version });
+    }
+
+    hit
+  }
+
+  /**Function writes the words that have not been sent yet to a file, one per line, starting with the word
+   * of the last test case taken so its requests are sent again. The wordlists are read again from the start
+   * and streamed to the file, so the scan can continue later with --resume.
+   * Params:
+   *  &self
+   *  file_name: &str {The name of the checkpoint file.}
+   * Returns Result<usize, IoError> {The number of words saved.}
+   */
+  pub fn save_checkpoint(&self, file_name: &str) -> Result<usize, IoError> {
+    let target = fs::canonicalize(file_name).ok();
+
+    for source in self.wordlists.iter() {
+      match source {
+        WordlistSource::Stdin => {
+          return Err(IoError::new(ErrorKind::Unsupported, "the wordlist is read from stdin and cannot be read again"));
+        },
+        WordlistSource::File(path) | WordlistSource::Checkpoint(path) if target.is_some() && fs::canonicalize(path).ok() == target => {
+          return Err(IoError::new(ErrorKind::AlreadyExists, format!("{} is being read, choose another file name", path)));
+        },
+        _ => {}
+      }
+    }
+
+    let file = OpenOptions::new().write(true).create(true).truncate(true).open(file_name)?;
+    let mut writer = BufWriter::new(file);
+    let mut saved: usize = 0;
+
+    let skip = self.current_word.load(Ordering::SeqCst);
+    for word in WordlistReader::new(self.wordlists.clone(), self.options, false).skip(skip) {
+      writer.write_all(word.as_bytes())?;
+      writer.write_all(b"\n")?;
+      saved += 1;
+    }
+
+    writer.flush()?;
+    Ok(saved)
+  }
+
+  /**Function is called by the Ctrl-C handler. The first press pauses the scan and the second press
+   * forces the workers to exit and the output thread to flush the output file before the process is terminated.
+   * Params:
+   *  &self
+   *  timeout: u64 {The request timeout in miliseconds, used to bound how long we wait for workers.}
+   * Returns nothing.
+   */
+  pub fn interrupt(&self, timeout: u64) {
+    // The pause menu cannot read commands while the wordlist is read from stdin, so the scan quits instead.
+    if !self.is_paused() && !self.should_quit() && !self.stdin_wordlist.load(Ordering::SeqCst) {
+      self.paused.store(true, Ordering::SeqCst);
+      return;
+    }
+
+    println!("\n{}: {}", style("Quit").red().bright(), style("Flushing pending output...").cyan());
+    self.quit.store(true, Ordering::SeqCst);
+
+    // In-flight requests are given the request timeout plus a grace period to finish.
+    let deadline = Instant::now() + Duration::from_millis(timeout) + Duration::from_secs(2);
+    while self.running.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+      thread::sleep(Duration::from_millis(20));
+    }
+
+    self.shutdown_output(Duration::from_secs(2));
+    std::process::exit(130);
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuAction {
+  Resume,
+  Threads(usize),
+  Quit,
+}
+
+/**Function displays the interactive pause menu and applies the chosen settings.
+ * Params:
+ *  control:         &FuzzControl  {The shared control structure.}
+ *  spawned_threads: usize         {The number of workers that have been started.}
+ * Returns MenuAction.
+ */
+pub fn pause_menu(control: &FuzzControl, spawned_threads: usize) -> MenuAction {
+  let completed = control.completed.load(Ordering::SeqCst);
+  let total = control.total.load(Ordering::SeqCst);
+  let more = match control.generating.load(Ordering::SeqCst) {     // More test cases are still being made.
+    true => "+",
+    false => "",
+  };
+
+  println!(
+    "\n{}: {} {}/{}{} {}",
+    style("Paused").yellow().bright(), style("sent").cyan(), style(completed).cyan(), style(total).cyan(),
+    style(more).cyan(), style("test cases").cyan()
+  );
+
+  loop {
+    println!(
+      "  {} resume  {} <MS> delay  {} <INT> threads  {} <CODE|len:LEN> filter  {} [FILE] checkpoint  {} quit",
+      style("[r]").green().bright(), style("[d]").green().bright(), style("[t]").green().bright(),
+      style("[f]").green().bright(), style("[s]").green().bright(), style("[q]").green().bright()
+    );
+    print!("{} ", style(">").yellow().bright());
+    let _ = io::stdout().flush();
+
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+      Ok(0) => { return MenuAction::Resume; },     // stdin is closed, nothing to ask.
+      Ok(_) => {},
+      Err(_) => { return MenuAction::Resume; }
+    }
+
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let command = words.first().copied().unwrap_or("");
+    let value = words.get(1).copied().unwrap_or("");
+
+    match command {
+      "r" | "resume" => {
+        return MenuAction::Resume;
+      },
+
+      "d" | "delay" => {
+        match value.parse::<u64>() {
+          Ok(ms) => {
+            control.delay.store(ms, Ordering::SeqCst);
+            println!("{}: delay set to {}ms", style("Ok").yellow().bright(), style(ms).cyan());
+          },
+          Err(_) => { println!("{}: expected a delay in miliseconds", style("Error").red().bright()); }
+        }
+      },
+
+      "t" | "threads" => {
+        match value.parse::<usize>() {
+          Ok(n) if n > 0 => {
+            control.active_threads.store(n, Ordering::SeqCst);
+            println!("{}: using {} threads", style("Ok").yellow().bright(), style(n).cyan());
+
+            if n > spawned_threads {
+              return MenuAction::Threads(n);
+            }
+          },
+          _ => { println!("{}: expected a thread count greater than 0", style("Error").red().bright()); }
+        }
+      },
+
+      "f" | "filter" => {
+        if let Some(len) = value.strip_prefix("len:") {
+          match len.parse::<u64>() {
+            Ok(l) => {
+              if let Ok(mut lens) = control.ignore_len.write() {
+                lens.push(l);
+              }
+              println!("{}: hiding responses with length {}", style("Ok").yellow().bright(), style(l).cyan());
+            },
+            Err(_) => { println!("{}: unable to parse length {}", style("Error").red().bright(), style(len).cyan()); }
+          }
+        }
+
+        else {
+          match value.parse::<u16>().ok().and_then(|c| StatusCode::from_u16(c).ok()) {
+            Some(code) => {
+              if let Ok(mut codes) = control.status_codes.write() {
+                codes.push(code);
+              }
+              println!("{}: displaying status code {}", style("Ok").yellow().bright(), style(code).cyan());
+            },
+            None => { println!("{}: unable to parse status code {}", style("Error").red().bright(), style(value).cyan()); }
+          }
+        }
+      },
+
+      "s" | "save" => {
+        let file_name = if !value.is_empty() { value } else { DEFAULT_CHECKPOINT };
+
+        match control.save_checkpoint(file_name) {
+          Ok(n) => {
+            println!(
+              "{}: saved {} words to {}, continue the scan with {} {}", style("Ok").yellow().bright(), style(n).cyan(),
+              style(file_name).cyan(), style("--resume").green().bright(), style(file_name).cyan()
+            );
+          },
+          Err(e) => {
+            println!("{}: unable to save checkpoint {} - {}", style("Error").red().bright(), style(file_name).cyan(), style(e).red());
+          }
+        }
+      },
+
+      "q" | "quit" => {
+        return MenuAction::Quit;
+      },
+
+      _ => {}
+    }
+  }
+}
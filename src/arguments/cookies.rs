@@ -0,0 +1,298 @@
+// Module loads the cookies sent with each request. Cookies come from --cookie "a=b; c=d" and from
+// Netscape cookies.txt files exported by browsers and curl. Without a session the cookies are sent as a
+// fixed Cookie header, with a session they are put in a jar that also stores Set-Cookie from responses.
+// Either way they are matched to the url of each request, so every target gets its own cookies, and they
+// are merged into a Cookie header that already comes from the request.
+use std::{
+  fs,
+  sync::Arc,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::{Url, cookie::{Jar, CookieStore}};
+
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";     // curl marks http only cookies with this prefix.
+
+#[derive(Debug, Clone)]
+pub enum Cookies {
+  None,
+  Header(Arc<FixedCookies>),  // Sent with every request that they match.
+  Session(Arc<Jar>),          // Shared by every worker and updated by Set-Cookie.
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FixedCookies {
+  pub pairs: Vec<String>,             // The name=value pairs from --cookie, sent to every target.
+  pub file: Vec<NetscapeCookie>,      // The cookies from a cookies.txt file, sent to the hosts they belong to.
+}
+
+impl Cookies {
+
+  /**Function returns the Cookie header for a request. A Cookie header from the request, e.g. from --header
+   * or an imported request, is kept and the cookies loaded here replace the ones with the same name.
+   * Params:
+   *  &self
+   *  url:     &str          {The request url.}
+   *  current: Option<&str>  {The Cookie header already in the request.}
+   * Returns Option<String> {None when the request is left as it is.}
+   */
+  pub fn merge_header(&self, url: &str, current: Option<&str>) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+
+    let added = match self {
+      Cookies::None => None,
+      Cookies::Header(fixed) => cookie_header(&fixed.pairs, &fixed.file, &url),
+      Cookies::Session(jar) => {
+        // The client only adds the jar cookies itself when the request has no Cookie header.
+        match current {
+          Some(_) => jar.cookies(&url).and_then(|v| v.to_str().ok().map(|s| s.to_owned())),
+          None => None,
+        }
+      }
+    }?;
+
+    match current {
+      Some(c) => { Some(merge_cookie_pairs(c, added.as_str())) },
+      None => { Some(added) }
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetscapeCookie {
+  pub domain: String,
+  pub include_subdomains: bool,
+  pub path: String,
+  pub secure: bool,
+  pub expires: u64,         // Unix time, 0 for session cookies.
+  pub name: String,
+  pub value: String,
+}
+
+impl NetscapeCookie {
+
+  /**Function parses a line from a cookies.txt file.
+   * Params:
+   *  line: &str {The tab separated line.}
+   * Returns Result<Option<NetscapeCookie>, String> {None for blank lines and comments.}
+   */
+  pub fn parse(line: &str) -> Result<Option<NetscapeCookie>, String> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let line = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+      Some(l) => l,
+      None => {
+        if line.trim().is_empty() || line.starts_with('#') {
+          return Ok(None);
+        }
+        line
+      }
+    };
+
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() < 7 {
+      return Err(format!("expected 7 tab separated fields but found {}", fields.len()));
+    }
+
+    let expires = match fields[4].parse::<u64>() {
+      Ok(n) => n,
+      Err(_) => { return Err(format!("invalid expiry {}", fields[4])); }
+    };
+
+    Ok(Some(NetscapeCookie {
+      domain: fields[0].trim_start_matches('.').to_lowercase(),
+      include_subdomains: fields[1].eq_ignore_ascii_case("TRUE"),
+      path: fields[2].to_owned(),
+      secure: fields[3].eq_ignore_ascii_case("TRUE"),
+      expires,
+      name: fields[5].to_owned(),
+      value: fields[6..].join("\t"),
+    }))
+  }
+
+  /**Function checks if the cookie has expired. */
+  pub fn is_expired(&self) -> bool {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    self.expires != 0 && self.expires < now
+  }
+
+  /**Function checks if the cookie would be sent to a url.
+   * Params:
+   *  &self
+   *  url: &Url {The request url.}
+   * Returns bool.
+   */
+  pub fn matches(&self, url: &Url) -> bool {
+    let host = match url.host_str() {
+      Some(h) => h.to_lowercase(),
+      None => { return false; }
+    };
+
+    let domain_match = host == self.domain || (self.include_subdomains && host.ends_with(format!(".{}", self.domain).as_str()));
+    let secure_match = !self.secure || url.scheme() == "https";
+
+    domain_match && secure_match && url.path().starts_with(self.path.as_str()) && !self.is_expired()
+  }
+
+  /**Function returns the cookie as a Set-Cookie header and the url it belongs to so it can be added to a jar. */
+  pub fn to_set_cookie(&self) -> Option<(String, Url)> {
+    let scheme = match self.secure {
+      true => "https",
+      false => "http",
+    };
+
+    let url = Url::parse(format!("{}://{}{}", scheme, self.domain, self.path).as_str()).ok()?;
+    let mut cookie = format!("{}={}; Path={}", self.name, self.value, self.path);
+
+    if self.include_subdomains {
+      cookie.push_str(format!("; Domain={}", self.domain).as_str());
+    }
+
+    if self.secure {
+      cookie.push_str("; Secure");
+    }
+
+    Some((cookie, url))
+  }
+}
+
+/**Function reads every cookie from a Netscape cookies.txt file.
+ * Params:
+ *  file_name: &str {The path to the cookies file.}
+ * Returns Result<Vec<NetscapeCookie>, String>
+ */
+pub fn load_cookies_txt(file_name: &str) -> Result<Vec<NetscapeCookie>, String> {
+  let contents = match fs::read_to_string(file_name) {
+    Ok(c) => c,
+    Err(e) => { return Err(format!("unable to read cookies file {} - {}", file_name, e)); }
+  };
+
+  let mut cookies: Vec<NetscapeCookie> = Default::default();
+
+  for (i, line) in contents.lines().enumerate() {
+    match NetscapeCookie::parse(line) {
+      Ok(Some(c)) => { cookies.push(c); },
+      Ok(None) => {},
+      Err(e) => { return Err(format!("{} line {}: {}", file_name, i+1, e)); }
+    }
+  }
+
+  Ok(cookies)
+}
+
+/**Function splits a Cookie header such as "a=b; c=d" into name=value pairs.
+ * Params:
+ *  spec: &str {The cookies.}
+ * Returns Result<Vec<String>, String>
+ */
+pub fn parse_cookie_header(spec: &str) -> Result<Vec<String>, String> {
+  let mut pairs: Vec<String> = Default::default();
+
+  for pair in spec.split(';').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+    match pair.split_once('=') {
+      Some((name, _)) if !name.trim().is_empty() => { pairs.push(pair.to_owned()); },
+      _ => { return Err(format!("invalid cookie {} - expected name=value", pair)); }
+    }
+  }
+
+  Ok(pairs)
+}
+
+/**Function joins two Cookie headers. Cookies in added replace the cookies with the same name in current.
+ * Params:
+ *  current: &str {The Cookie header of the request.}
+ *  added:   &str {The cookies that are added.}
+ * Returns String.
+ */
+pub fn merge_cookie_pairs(current: &str, added: &str) -> String {
+  let name = |pair: &str| -> String {
+    pair.split_once('=').map(|(n, _)| n).unwrap_or(pair).trim().to_owned()
+  };
+
+  let added: Vec<&str> = added.split(';').map(|p| p.trim()).filter(|p| !p.is_empty()).collect();
+  let names: Vec<String> = added.iter().map(|p| name(p)).collect();
+
+  let mut all: Vec<&str> = current.split(';').map(|p| p.trim()).filter(|p| !p.is_empty() && !names.contains(&name(p))).collect();
+  all.extend(added);
+  all.join("; ")
+}
+
+/**Function builds the fixed Cookie header sent with a request.
+ * Params:
+ *  pairs:   &[String]          {The name=value pairs from --cookie.}
+ *  file:    &[NetscapeCookie]  {The cookies loaded from a cookies.txt file.}
+ *  target:  &Url               {The request url.}
+ * Returns Option<String> {None when there are no cookies to send.}
+ */
+pub fn cookie_header(pairs: &[String], file: &[NetscapeCookie], target: &Url) -> Option<String> {
+  let mut all: Vec<String> = pairs.to_vec();
+  all.extend(file.iter().filter(|c| c.matches(target)).map(|c| format!("{}={}", c.name, c.value)));
+
+  match all.len() {
+    0 => None,
+    _ => Some(all.join("; ")),
+  }
+}
+
+/**Function creates the cookie jar shared by every worker when a session is kept.
+ * Params:
+ *  pairs:   &[String]          {The name=value pairs from --cookie, stored for every target host.}
+ *  file:    &[NetscapeCookie]  {The cookies loaded from a cookies.txt file.}
+ *  targets: &[Url]             {The urls being fuzzed.}
+ * Returns Arc<Jar>
+ */
+pub fn session_jar(pairs: &[String], file: &[NetscapeCookie], targets: &[Url]) -> Arc<Jar> {
+  let jar = Jar::default();
+
+  for target in targets {
+    for pair in pairs {
+      jar.add_cookie_str(format!("{}; Path=/", pair).as_str(), target);
+    }
+  }
+
+  for cookie in file.iter().filter(|c| !c.is_expired()) {
+    if let Some((set_cookie, url)) = cookie.to_set_cookie() {
+      jar.add_cookie_str(set_cookie.as_str(), &url);
+    }
+  }
+
+  Arc::new(jar)
+}
+
+/**Function loads the cookies from the command line options.
+ * Params:
+ *  cookie:  Option<&str> {The cookies from --cookie, e.g. "a=b; c=d"}
+ *  file:    Option<&str> {The path to a Netscape cookies.txt file.}
+ *  session: bool         {Keeps the cookies set by the server in a shared jar.}
+ *  targets: &[&str]      {The urls being fuzzed.}
+ * Returns Result<Cookies, String>
+ */
+pub fn load_cookies(cookie: Option<&str>, file: Option<&str>, session: bool, targets: &[&str]) -> Result<Cookies, String> {
+  let pairs = match cookie {
+    Some(c) => parse_cookie_header(c)?,
+    None => Default::default(),
+  };
+
+  let file_cookies = match file {
+    Some(f) => load_cookies_txt(f)?,
+    None => Default::default(),
+  };
+
+  if !session && pairs.is_empty() && file_cookies.is_empty() {
+    return Ok(Cookies::None);
+  }
+
+  if session {
+    let mut urls: Vec<Url> = Default::default();
+
+    for target in targets {
+      match Url::parse(target) {
+        Ok(u) => { urls.push(u); },
+        Err(e) => { return Err(format!("unable to match cookies to {} - {}", target, e)); }
+      }
+    }
+
+    return Ok(Cookies::Session(session_jar(&pairs, &file_cookies, &urls)));
+  }
+
+  Ok(Cookies::Header(Arc::new(FixedCookies { pairs, file: file_cookies })))
+}
@@ -0,0 +1,125 @@
+// Module fetches a fresh anti-forgery token before each request. The token is taken from the page at
+// --csrf-url with a regex (the first capture group, or the whole match) or a CSS selector (the value or
+// content attribute, or the text of the element) and replaces {csrf} in the url, headers and body.
+use std::sync::{Mutex, MutexGuard};
+
+use regex::Regex;
+use scraper::{Html, Selector};
+use reqwest::blocking::Client;
+
+use super::auth::Auth;
+use super::request::{RequestTemplate, TestCase};
+
+// Used when neither a regex nor a selector is given, matches the usual names of token fields.
+pub const DEFAULT_CSRF_SELECTOR: &str =
+  "input[name*=csrf i], input[name*=xsrf i], input[name*=token i], meta[name*=csrf i], meta[name*=xsrf i]";
+
+#[derive(Debug)]
+pub enum TokenExtractor {
+  Regex(Regex),
+  Selector(Selector),
+}
+
+#[derive(Debug)]
+pub struct CsrfSource {
+  pub url: String,                  // The page that contains the token.
+  pub extractor: TokenExtractor,
+  lock: Mutex<()>,                  // Held while a token is fetched and used.
+}
+
+impl CsrfSource {
+
+  /**Function creates the token source from the command line options.
+   * Params:
+   *  url:      &str          {The page that contains the token.}
+   *  regex:    Option<&str>  {A regex that matches the token.}
+   *  selector: Option<&str>  {A CSS selector for the element holding the token.}
+   * Returns Result<CsrfSource, String>
+   */
+  pub fn new(url: &str, regex: Option<&str>, selector: Option<&str>) -> Result<CsrfSource, String> {
+    let extractor = match (regex, selector) {
+      (Some(_), Some(_)) => {
+        return Err("use either --csrf-regex or --csrf-selector, not both".to_owned());
+      },
+      (Some(r), None) => {
+        match Regex::new(r) {
+          Ok(r) => TokenExtractor::Regex(r),
+          Err(e) => { return Err(format!("invalid csrf regex - {}", e)); }
+        }
+      },
+      (None, s) => {
+        let s = s.unwrap_or(DEFAULT_CSRF_SELECTOR);
+        match Selector::parse(s) {
+          Ok(s) => TokenExtractor::Selector(s),
+          Err(e) => { return Err(format!("invalid csrf selector {} - {}", s, e)); }
+        }
+      }
+    };
+
+    Ok(CsrfSource { url: url.to_owned(), extractor, lock: Mutex::new(()) })
+  }
+
+  /**Function serializes fetching and using a token between the workers. Many servers keep one token per
+   * session, so a token fetched by another worker in the meantime would invalidate ours.
+   * Params:
+   *  &self
+   * Returns MutexGuard<()> {The token can be fetched and used until the guard is dropped.}
+   */
+  pub fn lock(&self) -> MutexGuard<'_, ()> {
+    match self.lock.lock() {
+      Ok(g) => g,
+      Err(poisoned) => poisoned.into_inner(),
+    }
+  }
+
+  /**Function finds the token in a page.
+   * Params:
+   *  &self
+   *  page: &str {The html of the page.}
+   * Returns Option<String>
+   */
+  pub fn extract(&self, page: &str) -> Option<String> {
+    match &self.extractor {
+      TokenExtractor::Regex(r) => {
+        let captures = r.captures(page)?;
+        captures.get(1).or_else(|| captures.get(0)).map(|m| m.as_str().to_owned())
+      },
+      TokenExtractor::Selector(s) => {
+        let document = Html::parse_document(page);
+        let element = document.select(s).next()?;
+
+        match element.value().attr("value").or_else(|| element.value().attr("content")) {
+          Some(v) => Some(v.to_owned()),
+          None => Some(element.text().collect::<String>().trim().to_owned()),
+        }
+      }
+    }
+  }
+
+  /**Function requests the page and returns a fresh token.
+   * Params:
+   *  &self
+   *  client: &Client {The http client, sharing the session with the scan.}
+   *  auth:   &Auth   {The authentication added to the request.}
+   * Returns Result<String, String>
+   */
+  pub fn fetch(&self, client: &Client, auth: &Auth) -> Result<String, String> {
+    let case = TestCase { url: self.url.clone(), payload: String::new(), target: None, word: 0 };
+    let request = RequestTemplate::default().prepare(&case, None);
+
+    let response = match request.send(client, auth) {
+      Ok(r) => r,
+      Err(e) => { return Err(format!("unable to fetch the csrf token from {} - {}", self.url, e)); }
+    };
+
+    let page = match response.text() {
+      Ok(p) => p,
+      Err(e) => { return Err(format!("unable to read the csrf page {} - {}", self.url, e)); }
+    };
+
+    match self.extract(page.as_str()) {
+      Some(t) if !t.is_empty() => { Ok(t) },
+      _ => { Err(format!("no csrf token found in {}", self.url)) }
+    }
+  }
+}
@@ -0,0 +1,268 @@
+// Module transforms each payload before it is placed into the request.
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Sha256, Digest};
+
+pub const ENCODER_DELIMITER: char = ',';    // Separates encoders in the chain, \, keeps a comma in a value.
+pub const ENCODER_VALUE: char = ':';        // Separates an encoder from its value, e.g. prefix:admin
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Encoder {
+  Url,
+  DoubleUrl,
+  Html,
+  HtmlAll,
+  Base64,
+  Hex,
+  Unicode,
+  Upper,
+  Lower,
+  Prefix(String),
+  Suffix(String),
+  Md5,
+  Sha1,
+  Sha256,
+  Unescape,
+}
+
+impl Encoder {
+
+  /**Function parses a single encoder name.
+   * Params:
+   *  name: &str {The encoder, e.g. base64 or suffix:.bak}
+   * Returns Result<Encoder, String>
+   */
+  pub fn parse(name: &str) -> Result<Encoder, String> {
+    let (key, value) = match name.split_once(ENCODER_VALUE) {
+      Some((k, v)) => (k, Some(v)),
+      None => (name, None),
+    };
+
+    let encoder = match (key.trim().to_lowercase().as_str(), value) {
+      ("url", None) => Encoder::Url,
+      ("double-url", None) => Encoder::DoubleUrl,
+      ("html", None) => Encoder::Html,
+      ("html-all", None) => Encoder::HtmlAll,
+      ("base64", None) => Encoder::Base64,
+      ("hex", None) => Encoder::Hex,
+      ("unicode", None) => Encoder::Unicode,
+      ("upper", None) => Encoder::Upper,
+      ("lower", None) => Encoder::Lower,
+      ("prefix", Some(v)) => Encoder::Prefix(v.to_owned()),
+      ("suffix", Some(v)) => Encoder::Suffix(v.to_owned()),
+      ("md5", None) => Encoder::Md5,
+      ("sha1", None) => Encoder::Sha1,
+      ("sha256", None) => Encoder::Sha256,
+      ("unescape", None) => Encoder::Unescape,
+      ("prefix", None) | ("suffix", None) => {
+        return Err(format!("{} expects a value, e.g. {}:text", key, key));
+      },
+      _ => {
+        return Err(format!("unknown encoder {}", name));
+      }
+    };
+
+    Ok(encoder)
+  }
+
+  /**Function applies the encoder to a payload.
+   * Params:
+   *  &self
+   *  input: &str {The payload.}
+   * Returns String.
+   */
+  pub fn apply(&self, input: &str) -> String {
+    match self {
+      Encoder::Url => { url_encode(input) },
+      Encoder::DoubleUrl => { url_encode(url_encode(input).as_str()) },
+      Encoder::Html => { html_escape(input) },
+      Encoder::HtmlAll => { input.chars().map(|c| format!("&#{};", c as u32)).collect() },
+      Encoder::Base64 => { BASE64.encode(input.as_bytes()) },
+      Encoder::Hex => { to_hex(input.as_bytes()) },
+      Encoder::Unicode => { input.encode_utf16().map(|u| format!("\\u{:04x}", u)).collect() },
+      Encoder::Upper => { input.to_uppercase() },
+      Encoder::Lower => { input.to_lowercase() },
+      Encoder::Prefix(p) => { format!("{}{}", p, input) },
+      Encoder::Suffix(s) => { format!("{}{}", input, s) },
+      Encoder::Md5 => { to_hex(Md5::digest(input.as_bytes()).as_slice()) },
+      Encoder::Sha1 => { to_hex(Sha1::digest(input.as_bytes()).as_slice()) },
+      Encoder::Sha256 => { to_hex(Sha256::digest(input.as_bytes()).as_slice()) },
+      Encoder::Unescape => { unescape(input) }
+    }
+  }
+}
+
+/**Function parses a comma separated chain of encoders. A comma inside a value is written as \, e.g. suffix:\,1
+ * Params:
+ *  spec: &str {The encoders in the order they are applied, e.g. url,base64}
+ * Returns Result<Vec<Encoder>, String>
+ */
+pub fn parse_chain(spec: &str) -> Result<Vec<Encoder>, String> {
+  let mut names: Vec<String> = vec![String::new()];
+  let mut chars = spec.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' if chars.peek() == Some(&ENCODER_DELIMITER) => {
+        chars.next();
+        names.last_mut().unwrap().push(ENCODER_DELIMITER);       // Safe to unwrap because names is never empty.
+      },
+      ENCODER_DELIMITER => { names.push(String::new()); },
+      _ => { names.last_mut().unwrap().push(c); }
+    }
+  }
+
+  names.iter()
+    .filter(|e| !e.is_empty())
+    .map(|e| Encoder::parse(e.as_str()))
+    .collect()
+}
+
+/**Function applies every encoder in the chain to a payload, in order.
+ * Params:
+ *  chain:   &[Encoder] {The encoders.}
+ *  payload: &str       {The word from the wordlist.}
+ * Returns String.
+ */
+pub fn apply_chain(chain: &[Encoder], payload: &str) -> String {
+  let mut out = payload.to_owned();
+
+  for encoder in chain {
+    out = encoder.apply(out.as_str());
+  }
+
+  out
+}
+
+/**Function percent encodes every byte except the unreserved characters from RFC 3986. */
+pub fn url_encode(input: &str) -> String {
+  let mut out = String::with_capacity(input.len() * 3);
+
+  for b in input.bytes() {
+    match b {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => { out.push(b as char); },
+      _ => { out.push_str(format!("%{:02X}", b).as_str()); }
+    }
+  }
+
+  out
+}
+
+/**Function replaces the characters that are special in html, & < > " and ', with their entities. */
+pub fn html_escape(input: &str) -> String {
+  let mut out = String::with_capacity(input.len());
+
+  for c in input.chars() {
+    match c {
+      '&' => { out.push_str("&amp;"); },
+      '<' => { out.push_str("&lt;"); },
+      '>' => { out.push_str("&gt;"); },
+      '"' => { out.push_str("&quot;"); },
+      '\'' => { out.push_str("&#39;"); },
+      _ => { out.push(c); }
+    }
+  }
+
+  out
+}
+
+/**Function turns the escapes \r, \n, \t, \0, \\ and \xHH into the characters they stand for, so a word can
+ * hold line breaks for raw requests. Escapes above \x7f and unknown escapes are kept as they are.
+ * Params:
+ *  input: &str {The payload.}
+ * Returns String.
+ */
+pub fn unescape(input: &str) -> String {
+  let mut out = String::with_capacity(input.len());
+  let mut rest = input;
+
+  while let Some(i) = rest.find('\\') {
+    out.push_str(&rest[..i]);
+    let escape = &rest[i+1..];
+
+    let (c, used) = match escape.as_bytes().first() {
+      Some(b'r') => (Some('\r'), 1),
+      Some(b'n') => (Some('\n'), 1),
+      Some(b't') => (Some('\t'), 1),
+      Some(b'0') => (Some('\0'), 1),
+      Some(b'\\') => (Some('\\'), 1),
+      Some(b'x') => {
+        match escape.get(1..3).filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit())).map(|h| u8::from_str_radix(h, 16)) {
+          Some(Ok(b)) if b < 0x80 => (Some(b as char), 3),
+          _ => (None, 0),
+        }
+      },
+      _ => (None, 0),
+    };
+
+    match c {
+      Some(c) => { out.push(c); },
+      None => { out.push('\\'); }
+    }
+
+    rest = &escape[used..];
+  }
+
+  out.push_str(rest);
+  out
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn html_escapes_only_special_characters() {
+    assert_eq!(Encoder::Html.apply("<a href=\"x\">Tom & 'Jo'</a>"), "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jo&#39;&lt;/a&gt;");
+    assert_eq!(Encoder::Html.apply("admin é"), "admin é");
+    assert_eq!(Encoder::HtmlAll.apply("a<"), "&#97;&#60;");
+  }
+
+  #[test]
+  fn chain_is_applied_in_order() {
+    let chain = parse_chain("prefix:../,url,base64").unwrap();
+
+    assert_eq!(chain, vec![Encoder::Prefix("../".to_owned()), Encoder::Url, Encoder::Base64]);
+    assert_eq!(apply_chain(&chain, "etc passwd"), BASE64.encode("..%2Fetc%20passwd"));
+    assert_eq!(apply_chain(&parse_chain("upper,suffix:.bak").unwrap(), "index"), "INDEX.bak");
+    assert_eq!(apply_chain(&parse_chain("double-url").unwrap(), "a b"), "a%2520b");
+    assert_eq!(apply_chain(&[], "word"), "word");
+  }
+
+  #[test]
+  fn chain_accepts_escaped_commas_in_values() {
+    let chain = parse_chain("prefix:a\\,b,suffix:\\,1,lower").unwrap();
+
+    assert_eq!(chain, vec![Encoder::Prefix("a,b".to_owned()), Encoder::Suffix(",1".to_owned()), Encoder::Lower]);
+    assert_eq!(apply_chain(&chain, "X"), "a,bx,1");
+    assert_eq!(parse_chain("suffix:\\n").unwrap(), vec![Encoder::Suffix("\\n".to_owned())]);
+  }
+
+  #[test]
+  fn chain_rejects_unknown_encoders() {
+    assert!(parse_chain("url,rot13").is_err());
+    assert!(parse_chain("prefix").is_err());
+    assert!(parse_chain("url,,hex").is_ok());
+  }
+
+  #[test]
+  fn unescape_turns_escapes_into_characters() {
+    assert_eq!(Encoder::Unescape.apply("a\\r\\nHost: x\\t\\0"), "a\r\nHost: x\t\0");
+    assert_eq!(unescape("\\x41\\x7f\\\\n"), "A\x7f\\n");
+    assert_eq!(unescape("\\xff \\x4 \\q \\"), "\\xff \\x4 \\q \\");       // Kept as they are.
+    assert_eq!(parse_chain("unescape").unwrap(), vec![Encoder::Unescape]);
+  }
+
+  #[test]
+  fn hashes_and_hex_are_lowercase_hex() {
+    assert_eq!(Encoder::Md5.apply("admin"), "21232f297a57a5a743894a0e4a801fc3");
+    assert_eq!(Encoder::Sha1.apply("admin"), "d033e22ae348aeb5660fc2140aec35850c4da997");
+    assert_eq!(Encoder::Hex.apply("A~"), "417e");
+    assert_eq!(Encoder::Unicode.apply("a🦀"), "\\u0061\\ud83e\\udd80");
+  }
+}
@@ -0,0 +1,171 @@
+// Module sends requests from a tokio runtime instead of worker threads. A semaphore limits the requests in
+// flight to the thread count, so hundreds of requests can wait on slow servers without an OS thread each.
+// The engine takes test cases from the same queue and reports through the same output thread, so the
+// pause menu, filters and checkpoints work the same way.
+use std::{
+  sync::{Arc, mpsc::Sender},
+  sync::atomic::Ordering,
+  thread,
+  time::Duration,
+};
+
+use reqwest::Client;
+use tokio::{runtime, task, sync::Semaphore, time::sleep};
+
+use super::{ThreadMessage, FuzzControl, OutputEvent};
+use super::auth::Auth;
+use super::cookies::Cookies;
+use super::request::{self, RequestTemplate, TestCase};
+
+// Blocking clients cannot be dropped inside the runtime, so the engine only holds async clients.
+#[derive(Debug, Clone)]
+pub struct AsyncSettings {
+  pub debug: bool,
+  pub verbose: bool,
+  pub html: bool,
+  pub no_timeout_err: bool,
+  pub client: Client,
+  pub auth: Auth,
+  pub template: RequestTemplate,
+  pub replay: Option<Client>,
+  pub cookies: Cookies,
+  pub show_version: bool,
+}
+
+/**Function sends one test case and passes the response to the output thread.
+ * Params:
+ *  settings: &AsyncSettings  {The clients and request template.}
+ *  control:  &FuzzControl    {The state shared with the main thread.}
+ *  case:     TestCase        {The url and payload.}
+ * Returns nothing.
+ */
+async fn process_case(settings: &AsyncSettings, control: &FuzzControl, case: TestCase) {
+  let label = settings.template.label(&case);
+  let mut prepared = settings.template.prepare(&case, None);
+  prepared.add_cookies(&settings.cookies);
+
+  match prepared.send_async(&settings.client, &settings.auth).await {
+    Ok(response) => {
+      let status = response.status();
+      let len = response.content_length().unwrap_or(0);
+      let location = request::redirect_location(status, response.headers());
+      let version = match settings.show_version {
+        true => Some(response.version()),
+        false => None,
+      };
+
+      let hit = control.report(label.as_str(), status, len, location, version, settings.verbose, settings.debug);
+
+      if let (true, Some(replay)) = (hit, settings.replay.as_ref()) {         // Sends the hit through the replay proxy.
+        match prepared.send_async(replay, &settings.auth).await {
+          Ok(_) => {},
+          Err(e) => { control.emit(OutputEvent::Error(format!("replay proxy - {}", e))); }
+        }
+      }
+
+      if settings.html {
+        match response.text().await {
+          Ok(body) => { control.emit(OutputEvent::Body(body)); },
+          Err(e) => { control.emit(OutputEvent::Error(e.to_string())); }
+        }
+      }
+    },
+    Err(e) if !e.is_builder() && !settings.no_timeout_err => {
+      control.emit(OutputEvent::Error(e.to_string()));
+    },
+    Err(_) => {}
+  }
+}
+
+/**Function takes test cases off the queue and starts a task for each one while a permit is free. The
+ * number of permits follows the thread count, which can be changed from the pause menu.
+ * Params:
+ *  settings: Arc<AsyncSettings>  {The clients and request template.}
+ *  control:  Arc<FuzzControl>    {The state shared with the main thread.}
+ * Returns nothing.
+ */
+async fn dispatch(settings: Arc<AsyncSettings>, control: Arc<FuzzControl>) {
+  let mut permits = control.active_threads.load(Ordering::SeqCst).max(1);
+  let limiter = Arc::new(Semaphore::new(permits));
+
+  loop {
+    let wanted = control.active_threads.load(Ordering::SeqCst).max(1);
+    if wanted > permits {
+      limiter.add_permits(wanted - permits);
+      permits = wanted;
+    }
+
+    if control.should_quit() {
+      break;
+    }
+
+    if control.is_paused() {
+      sleep(Duration::from_millis(50)).await;
+      continue;
+    }
+
+    let permit = match limiter.clone().acquire_owned().await {
+      Ok(p) => p,
+      Err(_) => { break; }
+    };
+
+    // Permits held by requests in flight are removed as they are returned when the thread count is lowered.
+    if permits > control.active_threads.load(Ordering::SeqCst).max(1) {
+      permit.forget();
+      permits -= 1;
+      continue;
+    }
+
+    // The queue blocks while the wordlists are read, so the wait is moved off the runtime's workers.
+    let case = match task::block_in_place(|| control.next_case()) {
+      Some(c) => c,
+      None => { break; }
+    };
+
+    let task_settings = settings.clone();
+    let task_control = control.clone();
+
+    tokio::spawn(async move {
+      process_case(&task_settings, &task_control, case).await;
+      task_control.completed.fetch_add(1, Ordering::SeqCst);
+
+      // A delay set from the pause menu is waited out by each permit, like a worker thread between requests.
+      let delay = task_control.delay.load(Ordering::SeqCst);
+      if delay > 0 {
+        sleep(Duration::from_millis(delay)).await;
+      }
+
+      drop(permit);
+    });
+  }
+
+  // Waits for the requests in flight by taking back every permit.
+  let _ = limiter.acquire_many(permits as u32).await;
+}
+
+/**Function starts the async engine on its own thread so the main thread can show the pause menu.
+ * Params:
+ *  settings: AsyncSettings           {The clients and request template.}
+ *  control:  Arc<FuzzControl>        {The state shared with the main thread and the Ctrl-C handler.}
+ *  sender:   Sender<ThreadMessage>   {Tells the main thread when the engine is finished.}
+ * Returns Result<JoinHandle<()>, String>
+ */
+pub fn start_async_engine(settings: AsyncSettings, control: Arc<FuzzControl>, sender: Sender<ThreadMessage>) -> Result<thread::JoinHandle<()>, String> {
+  let runtime = match runtime::Builder::new_multi_thread().enable_all().build() {
+    Ok(r) => r,
+    Err(e) => { return Err(format!("unable to start the async engine - {}", e)); }
+  };
+
+  // The semaphore limits the requests in flight, so the engine sends without a delay until one is set from the pause menu.
+  control.delay.store(0, Ordering::SeqCst);
+  control.running.fetch_add(1, Ordering::SeqCst);
+
+  let handle = thread::spawn(move || {
+    runtime.block_on(dispatch(Arc::new(settings), control.clone()));
+
+    control.running.fetch_sub(1, Ordering::SeqCst);
+    let _ = sender.send(ThreadMessage::Finished);
+  });
+
+  Ok(handle)
+}
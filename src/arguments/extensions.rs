@@ -0,0 +1,95 @@
+// Module builds the file names requested for each word when fuzzing directory paths.
+// Plain extensions are appended to the word (php -> {word}.php) while templates place the word
+// anywhere in the name ({word}~, .{word}.swp).
+use std::collections::HashSet;
+
+pub const WORD_MARKER: &str = "{word}";     // Replaced with the word in extension templates.
+pub const EXT_DELIMITER: &str = ",";        // Separates extensions and templates.
+
+// The backup files preset. Each template is applied to the bare word, unless it is skipped, and to every generated extension.
+pub const BACKUP_TEMPLATES: [&str; 12] = [
+  "{word}.bak", "{word}~", ".{word}.swp", ".{word}.swo", "{word}.old", "{word}.orig",
+  "{word}.save", "{word}.tmp", "{word}.backup", "{word}.copy", "{word}.1", "{word}.zip",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionPlan {
+  pub templates: Vec<String>,   // Extensions converted to templates.
+  pub backups: bool,            // Adds the backup files preset.
+  pub bare: bool,               // Requests the bare word as well.
+}
+
+impl ExtensionPlan {
+
+  /**Function creates the plan from the command line options.
+   * Params:
+   *  ext:     Option<&str> {The comma separated extensions and templates.}
+   *  backups: bool         {Adds the backup files preset.}
+   *  bare:    bool         {Requests the bare word as well.}
+   * Returns ExtensionPlan.
+   */
+  pub fn new(ext: Option<&str>, backups: bool, bare: bool) -> ExtensionPlan {
+    let mut templates: Vec<String> = Default::default();
+
+    if let Some(e) = ext {
+      for i in e.split(EXT_DELIMITER).filter(|i| !i.is_empty()) {
+        if i.contains(WORD_MARKER) {
+          templates.push(i.to_owned());
+        }
+        else {
+          templates.push(format!("{WORD_MARKER}.{i}"));
+        }
+      }
+    }
+
+    ExtensionPlan { templates, backups, bare }
+  }
+
+  /**Function checks that the plan requests at least one file name for each word.
+   * Params:
+   *  &self
+   * Returns Result<(), String>
+   */
+  pub fn check(&self) -> Result<(), String> {
+    if !self.bare && self.templates.is_empty() {
+      return Err("--no-bare needs --ext, otherwise there is no file name left to request".to_owned());
+    }
+
+    Ok(())
+  }
+
+  /**Function generates every file name for a word. Extensions come first, then the bare word and then
+   * the backup files for the bare word and each extension. A name is only returned once.
+   * Params:
+   *  &self
+   *  word: &str {The word from the wordlist.}
+   * Returns Vec<String>
+   */
+  pub fn expand(&self, word: &str) -> Vec<String> {
+    let mut names: Vec<String> = self.templates.iter().map(|t| t.replace(WORD_MARKER, word)).collect();
+
+    let mut files: Vec<String> = Default::default();
+    if self.bare {
+      files.push(word.to_owned());
+    }
+    files.extend(names.iter().cloned());
+
+    if self.bare {
+      names.push(word.to_owned());
+    }
+
+    if self.backups {
+      for file in files.iter() {
+        for template in BACKUP_TEMPLATES.iter() {
+          names.push(template.replace(WORD_MARKER, file.as_str()));
+        }
+      }
+    }
+
+    // A word that already ends like a template, e.g. index.php or config.bak, can make the same name twice.
+    let mut seen: HashSet<String> = Default::default();
+    names.retain(|n| seen.insert(n.clone()));
+
+    names
+  }
+}
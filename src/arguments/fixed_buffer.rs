@@ -1,234 +1,473 @@
+// Module includes a C style fixed buffer array that can be used with u8 (UTF-8) and u16 (UTF-16) units.
+use std::io::{BufWriter, Write, Error, ErrorKind};
+use std::fs::OpenOptions;
 
-// Module includes 2 C style fixed buffer arrays that can be used with u8 and u16
-#[allow(dead_code)]
-pub mod u16 {
-  use std::io::{BufWriter, Write, Error};                                                 
-  use std::fs::OpenOptions;
-  
-  pub const FIXED_BUF_SIZE: usize = 2048;
+pub const FIXED_BUF_SIZE: usize = 2048;
 
-  #[derive(Copy, Clone, Debug)]
-  pub struct U16FixedBuffer {
-    pub buffer: [u16; FIXED_BUF_SIZE],  // Arrays have a fixed size of 2048.
-    pub len: usize,                     // len tracks the content of the string
-    pub cap: usize,                     // Cap stores the max size of the array.
+pub type U8FixedBuffer = FixedBuffer<u8, FIXED_BUF_SIZE>;
+
+/**Trait describes how characters are encoded into and decoded from the units stored in a FixedBuffer. */
+pub trait BufferUnit: Copy + Default {
+  /**Function encodes a char into dst and returns the number of units that were used. */
+  fn encode_char(ch: char, dst: &mut [Self]) -> usize;
+
+  /**Function returns the number of units taken by a string once encoded. */
+  fn encoded_len(slice: &str) -> usize;
+
+  /**Function returns the index where the last char in units begins. */
+  #[cfg(test)]
+  fn last_char_start(units: &[Self]) -> usize;
+
+  /**Function decodes units into a string. Invalid sequences are replaced with U+FFFD. */
+  fn decode(units: &[Self]) -> String;
+}
+
+impl BufferUnit for u8 {
+  fn encode_char(ch: char, dst: &mut [u8]) -> usize {
+    ch.encode_utf8(dst).len()
   }
 
+  fn encoded_len(slice: &str) -> usize {
+    slice.len()
+  }
 
-  impl U16FixedBuffer {
+  #[cfg(test)]
+  fn last_char_start(units: &[u8]) -> usize {
+    let mut pos = units.len().saturating_sub(1);
 
-    /**Function Creates a new Fixed buffer filled with zeros.
-     * Params:
-     *  None.
-     * Returns U16FixedBuffer.
-    */
-    pub fn new() -> U16FixedBuffer {
-      U16FixedBuffer { buffer: [0u16; FIXED_BUF_SIZE], len: 0, cap: FIXED_BUF_SIZE }
+    // UTF-8 continuation bytes always start with the bits 10.
+    while pos > 0 && units[pos] & 0b1100_0000 == 0b1000_0000 {
+      pos -= 1;
     }
-  
-    /**Function Pushes a slice u8 slice into the contents of the array.
-     * Params:
-     *  &self,
-     *  slice: &str {The u8 slice you want to push into the array.}
-     * Returns usize.
-    */
-    pub fn push_str(&mut self, slice: &str) -> usize {
-      if slice.len().clone() + self.len.clone() > self.cap.clone() {
-        return 0 as usize;
-      }
-  
-      let mut pos: usize = self.len.clone();
-      for i in slice.chars() {
-        self.buffer[pos] = i as u16;
-        pos += 1;
-      }
-  
-      self.len = pos;
-      
-      pos
-    }
-  
-    /**Function pushes a single character into the array.
-     * Params:
-     *  &self,
-     *  ch: char {The char you want to push.}
-     * Returns usize.
-    */
-    pub fn push(&mut self, ch: char) -> usize {
-      if self.len.clone()+1 > self.cap.clone() {
-        return 0 as usize;
-      }
-  
-      let mut pos: usize = self.len.clone();
-      self.buffer[pos] = ch as u16;
-      pos += 1;
-  
-      self.len = pos;
-  
-      pos
-    }
-  
-    /**Function removes the last character in the array.
-     * Params:
-     *  &self.
-     * Returns nothing.
-    */
-    pub fn pop(&mut self) -> () {
-      self.buffer[self.len] = 0u16;
+
+    pos
+  }
+
+  fn decode(units: &[u8]) -> String {
+    String::from_utf8_lossy(units).into_owned()
+  }
+}
+
+impl BufferUnit for u16 {
+  fn encode_char(ch: char, dst: &mut [u16]) -> usize {
+    ch.encode_utf16(dst).len()
+  }
+
+  fn encoded_len(slice: &str) -> usize {
+    slice.encode_utf16().count()
+  }
+
+  #[cfg(test)]
+  fn last_char_start(units: &[u16]) -> usize {
+    let pos = units.len().saturating_sub(1);
+
+    // A low surrogate is the second half of a surrogate pair.
+    if pos > 0 && (0xDC00..=0xDFFF).contains(&units[pos]) && (0xD800..=0xDBFF).contains(&units[pos-1]) {
+      return pos-1;
     }
-  
-    /**Function clears the buffer and sets the contents to zero. Simmilar to memset in C
-     * Params:
-     *  &self.
-     * Returns nothing.
-    */
-    pub fn clear(&mut self) -> () {
-      let mut counter: usize = self.len.clone();
-      
-      for i in 0..self.len {
-        self.buffer[i] = 0u16;
-        counter -= 1;
+
+    pos
+  }
+
+  fn decode(units: &[u16]) -> String {
+    String::from_utf16_lossy(units)
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct FixedBuffer<T: BufferUnit, const N: usize> {
+  pub buffer: [T; N],             // Arrays have a fixed size of N units.
+  pub len: usize,                 // len tracks the content of the string
+  pub output: Option<String>,     // The file the buffer is flushed to when it overflows.
+  partial: [u8; 4],               // The start of a UTF-8 char that was split across two writes.
+  partial_len: usize,
+}
+
+impl<T: BufferUnit, const N: usize> Default for FixedBuffer<T, N> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: BufferUnit, const N: usize> FixedBuffer<T, N> {
+
+  /**Function Creates a new Fixed buffer filled with zeros.
+   * Params:
+   *  None.
+   * Returns FixedBuffer.
+  */
+  pub fn new() -> FixedBuffer<T, N> {
+    FixedBuffer { buffer: [T::default(); N], len: 0, output: None, partial: [0; 4], partial_len: 0 }
+  }
+
+  /**Function Creates a new Fixed buffer that is flushed to a file whenever it overflows.
+   * Params:
+   *  file_name: &str {The name of the file the contents are appended to.}
+   * Returns FixedBuffer.
+  */
+  pub fn with_output(file_name: &str) -> FixedBuffer<T, N> {
+    FixedBuffer { buffer: [T::default(); N], len: 0, output: Some(file_name.to_owned()), partial: [0; 4], partial_len: 0 }
+  }
+
+  /**Function returns the units that hold content. */
+  pub fn as_slice(&self) -> &[T] {
+    &self.buffer[0..self.len]
+  }
+
+  /**Function returns the number of units that can still be pushed before the buffer overflows. */
+  pub fn remaining(&self) -> usize {
+    N - self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /**Function Pushes a string slice into the contents of the array. When the slice does not fit, the buffer
+   * is flushed to the output file first, and slices larger than the whole buffer are written straight through.
+   * Params:
+   *  &self,
+   *  slice: &str {The string slice you want to push into the array.}
+   * Returns Result<usize, Error> {The number of units that were pushed or written.}
+  */
+  pub fn push_str(&mut self, slice: &str) -> Result<usize, Error> {
+    let units = T::encoded_len(slice);
+
+    if units > self.remaining() {
+      self.overflow()?;
+
+      if units > N {
+        let bytes = self.write_through(slice)?;
+        return Ok(bytes);
       }
-  
-      self.len = counter;
     }
-  
-    /**Function takes the string content of the buffer and writes it to a file.
-     * Params:
-     *  &self,
-     *  file_name: &str {The name of the file.}
-     * Returns Result<usize, Error>
-     */
-    pub fn write_data(&self, file_name: &str) -> Result<usize, Error> {
-      let temp_string = String::from_utf16_lossy(&self.buffer);
-      
-      match OpenOptions::new().read(true).append(true).open(file_name) {
-        Ok(s) => {
-          let mut writer = BufWriter::new(s);
-
-          match writer.write(temp_string.as_str().as_bytes()) {
-            Ok(s) => { return Ok(s); }
-            Err(e) => { return Err(e); }
-          }
-        },
-        Err(e) => { return Err(e); }
-      }
+
+    for ch in slice.chars() {
+      let pos = self.len;
+      self.len += T::encode_char(ch, &mut self.buffer[pos..]);
     }
+
+    Ok(units)
   }
-}
 
-#[allow(dead_code)]
-pub mod u8 {
-  use std::io::{BufWriter, Write, Error};
-  use std::fs::OpenOptions;
+  /**Function pushes a single character into the array, flushing the buffer first when it is full.
+   * Params:
+   *  &self,
+   *  ch: char {The char you want to push.}
+   * Returns Result<usize, Error> {The number of units that were pushed.}
+  */
+  #[cfg(test)]
+  pub fn push(&mut self, ch: char) -> Result<usize, Error> {
+    let mut encoded = [T::default(); 4];                  // Large enough for any char in UTF-8 or UTF-16.
+    let units = T::encode_char(ch, &mut encoded);
+
+    if units > self.remaining() {
+      self.overflow()?;
+    }
 
-  pub const FIXED_BUF_SIZE: usize = 2048;
+    self.buffer[self.len..self.len+units].copy_from_slice(&encoded[0..units]);
+    self.len += units;
 
-  #[derive(Copy, Clone, Debug)]
-  pub struct U8FixedBuffer {
-    pub buffer: [u8; FIXED_BUF_SIZE],
-    pub len: usize,
-    pub cap: usize,
+    Ok(units)
   }
 
-  impl U8FixedBuffer {
-
-    /**Function Creates a new Fixed buffer filled with zeros.
-     * Params:
-     *  None.
-     * Returns U8FixedBuffer.
-    */
-    pub fn new() -> U8FixedBuffer {
-      U8FixedBuffer { buffer: [0u8; FIXED_BUF_SIZE], len: 0, cap: FIXED_BUF_SIZE }
+  /**Function removes the last character in the array.
+   * Params:
+   *  &self.
+   * Returns Option<char> {The character that was removed.}
+  */
+  #[cfg(test)]
+  pub fn pop(&mut self) -> Option<char> {
+    if self.len == 0 {
+      return None;
     }
 
-    /**Function Pushes a slice u8 slice into the contents of the array.
-     * Params:
-     *  &self,
-     *  slice: &str {The u8 slice you want to push into the array.}
-     * Returns usize.
-    */
-    pub fn push_str(&mut self, slice: &str) -> usize {
-      if slice.len().clone() + self.len.clone() > self.cap.clone() {
-        return 0 as usize;
-      }
+    let start = T::last_char_start(self.as_slice());
+    let ch = T::decode(&self.buffer[start..self.len]).chars().next();
 
-      let mut pos: usize = self.len.clone();
-      for i in slice.chars() {
-        self.buffer[pos] = i as u8;
-        pos += 1;
-      }
+    for i in start..self.len {
+      self.buffer[i] = T::default();
+    }
+
+    self.len = start;
+    ch
+  }
 
-      self.len = pos;
-      
-      pos
+  /**Function clears the buffer and sets the contents to zero. Simmilar to memset in C
+   * Params:
+   *  &self.
+   * Returns nothing.
+  */
+  pub fn clear(&mut self) {
+    for i in 0..self.len {
+      self.buffer[i] = T::default();
     }
 
-    /**Function pushes a single character into the array.
-     * Params:
-     *  &self,
-     *  ch: char {The char you want to push.}
-     * Returns usize.
-    */
-    pub fn push(&mut self, ch: char) -> usize {
-      if self.len.clone()+1 > self.cap.clone() {
-        return 0 as usize;
-      }
+    self.len = 0;
+  }
+
+  /**Function takes the string content of the buffer and writes it to a file as UTF-8.
+   * Params:
+   *  &self,
+   *  file_name: &str {The name of the file.}
+   * Returns Result<usize, Error>
+   */
+  pub fn write_data(&self, file_name: &str) -> Result<usize, Error> {
+    Self::append(file_name, T::decode(self.as_slice()).as_bytes())
+  }
+
+  /**Function writes the contents of the buffer to the output file and clears it.
+   * Params:
+   *  &self.
+   * Returns Result<usize, Error> {The number of bytes that were written.}
+   */
+  pub fn flush_data(&mut self) -> Result<usize, Error> {
+    if self.len == 0 {
+      return Ok(0);
+    }
 
-      let mut pos: usize = self.len.clone();
-      self.buffer[pos] = ch as u8;
-      pos += 1;
+    let written = match self.output.clone() {
+      Some(file_name) => { self.write_data(file_name.as_str())? },
+      None => { return Ok(0); }
+    };
 
-      self.len = pos;
+    self.clear();
+    Ok(written)
+  }
 
-      pos
+  /**Function empties a full buffer into the output file, or returns an error if there is nowhere to put the data. */
+  fn overflow(&mut self) -> Result<(), Error> {
+    if self.output.is_none() {
+      return Err(Error::new(ErrorKind::WriteZero, "fixed buffer is full and has no output file"));
     }
 
-    /**Function removes the last character in the array.
-     * Params:
-     *  &self.
-     * Returns nothing.
-    */
-    pub fn pop(&mut self) -> () {
-      self.buffer[self.len] = 0u8;
+    self.flush_data()?;
+    Ok(())
+  }
+
+  /**Function writes a slice that is larger than the buffer directly to the output file. */
+  fn write_through(&self, slice: &str) -> Result<usize, Error> {
+    match self.output.as_deref() {
+      Some(file_name) => { Self::append(file_name, slice.as_bytes()) },
+      None => { Err(Error::new(ErrorKind::WriteZero, "fixed buffer is full and has no output file")) }
     }
+  }
+
+  fn append(file_name: &str, bytes: &[u8]) -> Result<usize, Error> {
+    let file = OpenOptions::new().append(true).create(true).open(file_name)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(bytes)?;
+    writer.flush()?;
 
-    /**Function clears the buffer and sets the contents to zero. Simmilar to memset in C
-     * Params:
-     *  &self.
-     * Returns nothing.
-    */
-    pub fn clear(&mut self) -> () {
-      let mut counter: usize = self.len.clone();
-      
-      for i in 0..self.len {
-        self.buffer[i] = 0u8;
-        counter -= 1;
+    Ok(bytes.len())
+  }
+}
+
+impl<T: BufferUnit, const N: usize> std::fmt::Display for FixedBuffer<T, N> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", T::decode(self.as_slice()))
+  }
+}
+
+impl<T: BufferUnit, const N: usize> Write for FixedBuffer<T, N> {
+  /**Function accepts UTF-8 bytes. An incomplete character at the end of buf is held back and completed by the
+   * next call, so write_all can split its input anywhere. Bytes that can never form a character are an InvalidData error.
+   */
+  fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+    let mut consumed = 0;
+
+    if self.partial_len > 0 {                                             // Finish the char left by the last call.
+      let width = utf8_width(self.partial[0]);
+      let take = (width - self.partial_len).min(buf.len());
+
+      // UTF-8 continuation bytes always start with the bits 10.
+      if buf[0..take].iter().any(|b| b & 0b1100_0000 != 0b1000_0000) {
+        self.partial_len = 0;
+        return Err(Error::new(ErrorKind::InvalidData, "incomplete UTF-8 character"));
       }
 
-      self.len = counter;
-    }
+      self.partial[self.partial_len..self.partial_len+take].copy_from_slice(&buf[0..take]);
+      self.partial_len += take;
+      consumed = take;
 
-    /**Function takes the string content of the buffer and writes it to a file.
-     * Params:
-     *  &self,
-     *  file_name: &str {The name of the file.}
-     * Returns Result<usize, Error>
-     */
-    pub fn write_data(&self, file_name: &str) -> Result<usize, Error> {
-      let buffer = &self.buffer[0..self.len];
-
-      match OpenOptions::new().read(true).append(true).open(file_name) {
-        Ok(s) => {
-          let mut writer = BufWriter::new(s);
-
-          match writer.write(buffer) {
-            Ok(s) => { return Ok(s); }
-            Err(e) => { return Err(e); }
-          }
-        },
-        Err(e) => { return Err(e); }
+      if self.partial_len < width {
+        return Ok(consumed);
       }
+
+      self.partial_len = 0;
+      let ch = match std::str::from_utf8(&self.partial[0..width]) {
+        Ok(s) => s.to_owned(),
+        Err(e) => { return Err(Error::new(ErrorKind::InvalidData, e)); }
+      };
+
+      self.push_str(ch.as_str())?;
     }
+
+    let rest = &buf[consumed..];
+    let (valid, tail) = match std::str::from_utf8(rest) {
+      Ok(_) => (rest.len(), 0),
+      Err(e) if e.error_len().is_none() => (e.valid_up_to(), rest.len() - e.valid_up_to()),
+      Err(e) if e.valid_up_to() > 0 => (e.valid_up_to(), 0),             // The invalid bytes fail on the next call.
+      Err(e) => { return Err(Error::new(ErrorKind::InvalidData, e)); }
+    };
+
+    // Safe to unwrap because valid marks the end of the valid UTF-8 prefix.
+    self.push_str(std::str::from_utf8(&rest[0..valid]).unwrap())?;
+
+    self.partial[0..tail].copy_from_slice(&rest[valid..valid+tail]);
+    self.partial_len = tail;
+
+    Ok(consumed + valid + tail)
+  }
+
+  /**Function writes the buffer to the output file. A char that is still incomplete stays held back. */
+  fn flush(&mut self) -> Result<(), Error> {
+    self.flush_data()?;
+    Ok(())
+  }
+}
+
+/**Function returns the number of bytes in the UTF-8 sequence that starts with the lead byte. */
+fn utf8_width(lead: u8) -> usize {
+  match lead {
+    0xC0..=0xDF => 2,
+    0xE0..=0xEF => 3,
+    _ => 4,
   }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+  use crate::arguments::test_files::temp_file;
+
+  #[test]
+  fn push_str_tracks_len() {
+    let mut buf: FixedBuffer<u8, 16> = FixedBuffer::new();
+
+    assert_eq!(buf.push_str("admin").unwrap(), 5);
+    assert_eq!(buf.len, 5);
+    assert_eq!(buf.to_string(), "admin");
+  }
+
+  #[test]
+  fn push_encodes_utf8_and_utf16() {
+    let mut u8_buf: FixedBuffer<u8, 16> = FixedBuffer::new();
+    let mut u16_buf: FixedBuffer<u16, 16> = FixedBuffer::new();
+
+    u8_buf.push_str("é🦀").unwrap();
+    u16_buf.push_str("é🦀").unwrap();
+
+    assert_eq!(u8_buf.len, 6);
+    assert_eq!(u16_buf.len, 3);
+    assert_eq!(u8_buf.to_string(), "é🦀");
+    assert_eq!(u16_buf.to_string(), "é🦀");
+  }
+
+  #[test]
+  fn pop_removes_whole_characters() {
+    let mut u8_buf: FixedBuffer<u8, 16> = FixedBuffer::new();
+    let mut u16_buf: FixedBuffer<u16, 16> = FixedBuffer::new();
+    u8_buf.push_str("a🦀").unwrap();
+    u16_buf.push_str("a🦀").unwrap();
+
+    assert_eq!(u8_buf.pop(), Some('🦀'));
+    assert_eq!(u16_buf.pop(), Some('🦀'));
+    assert_eq!(u8_buf.len, 1);
+    assert_eq!(u16_buf.len, 1);
+    assert_eq!(u8_buf.pop(), Some('a'));
+    assert_eq!(u8_buf.pop(), None);
+    assert_eq!(u8_buf.len, 0);
+  }
+
+  #[test]
+  fn clear_zeroes_contents() {
+    let mut buf: FixedBuffer<u16, 8> = FixedBuffer::new();
+    buf.push_str("test").unwrap();
+    buf.clear();
+
+    assert_eq!(buf.len, 0);
+    assert!(buf.buffer.iter().all(|u| *u == 0));
+  }
+
+  #[test]
+  fn overflow_without_output_is_an_error() {
+    let mut buf: FixedBuffer<u8, 4> = FixedBuffer::new();
+    buf.push_str("abcd").unwrap();
+
+    assert_eq!(buf.push('e').unwrap_err().kind(), ErrorKind::WriteZero);
+    assert_eq!(buf.push_str("f").unwrap_err().kind(), ErrorKind::WriteZero);
+    assert_eq!(buf.to_string(), "abcd");
+  }
+
+  #[test]
+  fn overflow_flushes_to_output() {
+    let file = temp_file("buffer_overflow", "");
+    let mut buf: FixedBuffer<u8, 8> = FixedBuffer::with_output(&file);
+
+    buf.push_str("first\n").unwrap();
+    buf.push_str("second\n").unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "first\n");
+    assert_eq!(buf.to_string(), "second\n");
+
+    buf.push_str("longer than the buffer\n").unwrap();
+    buf.flush().unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "first\nsecond\nlonger than the buffer\n");
+
+    let _ = fs::remove_file(&file);
+  }
+
+  #[test]
+  fn write_data_skips_unused_units() {
+    let file = temp_file("buffer_u16", "");
+    let mut buf: FixedBuffer<u16, 64> = FixedBuffer::new();
+    buf.push_str("hit -- 200 OK\n").unwrap();
+
+    assert_eq!(buf.write_data(&file).unwrap(), 14);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "hit -- 200 OK\n");
+
+    let _ = fs::remove_file(&file);
+  }
+
+  #[test]
+  fn implements_write() {
+    let file = temp_file("buffer_write", "");
+    let mut buf: FixedBuffer<u16, 32> = FixedBuffer::with_output(&file);
+
+    writeln!(buf, "http://127.0.0.1/admin -- {}", 200).unwrap();
+    assert_eq!(buf.write(&[0xFF]).unwrap_err().kind(), ErrorKind::InvalidData);
+    buf.flush().unwrap();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "http://127.0.0.1/admin -- 200\n");
+    assert!(buf.is_empty());
+
+    let _ = fs::remove_file(&file);
+  }
+
+  #[test]
+  fn write_holds_back_split_characters() {
+    let mut buf: FixedBuffer<u8, 32> = FixedBuffer::new();
+    let crab = "🦀".as_bytes();
+
+    assert_eq!(buf.write(&[b'a', crab[0], crab[1]]).unwrap(), 3);
+    assert_eq!(buf.to_string(), "a");
+    assert_eq!(buf.write(&crab[2..3]).unwrap(), 1);
+    assert_eq!(buf.to_string(), "a");
+    assert_eq!(buf.write(&[crab[3], b'b']).unwrap(), 2);
+    assert_eq!(buf.to_string(), "a🦀b");
+
+    buf.write_all(&[crab[0]]).unwrap();
+    assert_eq!(buf.write(b"c").unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(buf.to_string(), "a🦀b");
+  }
+
+  #[test]
+  fn write_all_reports_invalid_bytes() {
+    let mut buf: FixedBuffer<u8, 32> = FixedBuffer::new();
+
+    assert_eq!(buf.write_all(b"ok\xFFno").unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(buf.to_string(), "ok");
+  }
+}
@@ -0,0 +1,394 @@
+// Module generates payloads instead of reading them from a wordlist. Generators can be used anywhere a
+// wordlist path is accepted:
+//
+//   range:1-10000             numbers from 1 to 10000
+//   range:0-1000:step10:pad5  every 10th number, zero padded to 5 digits
+//   charset:digits:4          every 4 digit combination
+//   charset:abc123:1-3        every combination of a, b, c, 1, 2 and 3 from 1 to 3 characters long
+//   date:2024-01-01:2024-12-31:%Y%m%d  every day between the dates in the strftime format (default %Y-%m-%d)
+//   uuid:100                  100 random version 4 uuids
+use std::{
+  fmt::Write,
+  io::{Read, Error as IoError},
+};
+
+use chrono::{Duration as DateDuration, NaiveDate, format::{Item, StrftimeItems}};
+use uuid::Uuid;
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+pub const MAX_GENERATED: u128 = 100_000_000;      // Generators that make more words than this are rejected.
+const DATE_INPUT_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Generator {
+  Range { start: u64, end: u64, step: u64, pad: usize },
+  Charset { chars: Vec<char>, min: usize, max: usize },
+  Date { start: NaiveDate, end: NaiveDate, format: String },
+  Uuid { count: usize },
+}
+
+/**Function parses a length or a length range such as 4 or 1-3. */
+fn parse_bounds(value: &str) -> Result<(usize, usize), String> {
+  let (min, max) = match value.split_once('-') {
+    Some((a, b)) => (a, b),
+    None => (value, value),
+  };
+
+  match (min.parse::<usize>(), max.parse::<usize>()) {
+    (Ok(a), Ok(b)) if a <= b => { Ok((a, b)) },
+    _ => { Err(format!("invalid range {}", value)) }
+  }
+}
+
+/**Function returns the characters of a named charset, or the characters themselves. */
+fn charset(name: &str) -> Vec<char> {
+  let chars = match name {
+    "lower" => "abcdefghijklmnopqrstuvwxyz",
+    "upper" => "ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+    "alpha" => "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ",
+    "digits" => "0123456789",
+    "hex" => "0123456789abcdef",
+    "alnum" => "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789",
+    _ => name,
+  };
+
+  let mut out: Vec<char> = Default::default();
+  for c in chars.chars() {
+    if !out.contains(&c) {
+      out.push(c);
+    }
+  }
+
+  out
+}
+
+impl Generator {
+
+  /**Function parses a generator from a wordlist argument.
+   * Params:
+   *  spec: &str {The wordlist argument, e.g. range:1-100:pad3}
+   * Returns Result<Option<Generator>, String> {None when the argument is not a generator.}
+   */
+  pub fn parse(spec: &str) -> Result<Option<Generator>, String> {
+    let (kind, value) = match spec.split_once(':') {
+      Some(s) => s,
+      None => { return Ok(None); }
+    };
+
+    let generator = match kind {
+      "range" => {
+        let mut parts = value.split(':');
+        let (start, end) = match parts.next().and_then(|r| r.split_once('-')) {
+          Some((a, b)) => {
+            match (a.parse::<u64>(), b.parse::<u64>()) {
+              (Ok(a), Ok(b)) => (a, b),
+              _ => { return Err(format!("invalid range {}", spec)); }
+            }
+          },
+          None => { return Err(format!("{} expects START-END", spec)); }
+        };
+
+        let mut step: u64 = 1;
+        let mut pad: usize = 0;
+
+        for option in parts {
+          if let Some(s) = option.strip_prefix("step") {
+            step = match s.parse::<u64>() {
+              Ok(n) if n > 0 => n,
+              _ => { return Err(format!("invalid step {}", option)); }
+            };
+          }
+          else if let Some(p) = option.strip_prefix("pad") {
+            pad = match p.parse::<usize>() {
+              Ok(n) => n,
+              Err(_) => { return Err(format!("invalid padding {}", option)); }
+            };
+          }
+          else {
+            return Err(format!("unknown range option {}", option));
+          }
+        }
+
+        Generator::Range { start, end, step, pad }
+      },
+
+      "charset" => {
+        let (name, bounds) = match value.rsplit_once(':') {
+          Some(s) => s,
+          None => { return Err(format!("{} expects charset:CHARS:LENGTH", spec)); }
+        };
+
+        let chars = charset(name);
+        let (min, max) = parse_bounds(bounds)?;
+
+        if chars.is_empty() || max == 0 {
+          return Err(format!("{} does not generate any words", spec));
+        }
+
+        Generator::Charset { chars, min: min.max(1), max }
+      },
+
+      "date" => {
+        let mut parts = value.splitn(3, ':');
+        let start = parts.next().unwrap_or("");
+        let end = parts.next().unwrap_or("");
+        let format = parts.next().unwrap_or(DEFAULT_DATE_FORMAT);
+
+        let (start, end) = match (NaiveDate::parse_from_str(start, DATE_INPUT_FORMAT), NaiveDate::parse_from_str(end, DATE_INPUT_FORMAT)) {
+          (Ok(a), Ok(b)) if a <= b => (a, b),
+          _ => { return Err(format!("{} expects date:YYYY-MM-DD:YYYY-MM-DD[:FORMAT]", spec)); }
+        };
+
+        // Time fields like %H cannot be formatted from a date and would panic while the words are generated.
+        let mut probe = String::new();
+        if StrftimeItems::new(format).any(|i| i == Item::Error) || write!(probe, "{}", start.format(format)).is_err() {
+          return Err(format!("invalid date format {}", format));
+        }
+
+        Generator::Date { start, end, format: format.to_owned() }
+      },
+
+      "uuid" => {
+        match value.parse::<usize>() {
+          Ok(n) => Generator::Uuid { count: n },
+          Err(_) => { return Err(format!("{} expects uuid:COUNT", spec)); }
+        }
+      },
+
+      _ => { return Ok(None); }
+    };
+
+    // Words are streamed to the workers, but a generator this large would not finish in any sensible time.
+    match generator.count() {
+      Some(n) if n <= MAX_GENERATED => {},
+      Some(n) => { return Err(format!("{} generates {} words, more than the limit of {}", spec, n, MAX_GENERATED)); },
+      None => { return Err(format!("{} generates more words than the limit of {}", spec, MAX_GENERATED)); }
+    }
+
+    Ok(Some(generator))
+  }
+
+  /**Function returns the number of words the generator produces, when it fits in a u128. */
+  pub fn count(&self) -> Option<u128> {
+    match self {
+      Generator::Range { start, end, step, .. } => {
+        Some((start.abs_diff(*end) / step) as u128 + 1)
+      },
+      Generator::Charset { chars, min, max } => {
+        let mut total: u128 = 0;
+        for len in *min..=*max {
+          total = total.checked_add((chars.len() as u128).checked_pow(len as u32)?)?;
+        }
+        Some(total)
+      },
+      Generator::Date { start, end, .. } => {
+        Some((*end - *start).num_days() as u128 + 1)
+      },
+      Generator::Uuid { count } => {
+        Some(*count as u128)
+      }
+    }
+  }
+
+  /**Function returns an iterator that lazily produces every word.
+   * Params:
+   *  &self
+   * Returns Box<dyn Iterator<Item = String> + Send>
+   */
+  pub fn words(&self) -> Box<dyn Iterator<Item = String> + Send> {
+    match self.clone() {
+      Generator::Range { start, end, step, pad } => {
+        let values: Box<dyn Iterator<Item = u64> + Send> = match start <= end {
+          true => Box::new((start..=end).step_by(step as usize)),
+          false => Box::new((end..=start).rev().step_by(step as usize)),
+        };
+
+        Box::new(values.map(move |n| format!("{:0width$}", n, width = pad)))
+      },
+
+      Generator::Charset { chars, min, max } => {
+        Box::new(CharsetWords { indices: vec![0; min], chars, max, done: false })
+      },
+
+      Generator::Date { start, end, format } => {
+        let days = (end - start).num_days();
+        Box::new((0..=days).map(move |d| (start + DateDuration::days(d)).format(format.as_str()).to_string()))
+      },
+
+      Generator::Uuid { count } => {
+        Box::new((0..count).map(|_| Uuid::new_v4().to_string()))
+      }
+    }
+  }
+}
+
+/**Struct counts through every combination of the charset like an odometer, shortest words first. */
+struct CharsetWords {
+  chars: Vec<char>,
+  indices: Vec<usize>,
+  max: usize,
+  done: bool,
+}
+
+impl Iterator for CharsetWords {
+  type Item = String;
+
+  fn next(&mut self) -> Option<String> {
+    if self.done {
+      return None;
+    }
+
+    let word: String = self.indices.iter().map(|i| self.chars[*i]).collect();
+
+    // Moves to the next combination, growing the word when every position has wrapped around.
+    let mut pos = self.indices.len();
+    loop {
+      if pos == 0 {
+        if self.indices.len() >= self.max {
+          self.done = true;
+        }
+        else {
+          self.indices = vec![0; self.indices.len()+1];
+        }
+        break;
+      }
+
+      pos -= 1;
+      self.indices[pos] += 1;
+
+      if self.indices[pos] < self.chars.len() {
+        break;
+      }
+
+      self.indices[pos] = 0;
+    }
+
+    Some(word)
+  }
+}
+
+/**Struct exposes a generator as a reader with one word per line so it can be used like a wordlist. */
+pub struct GeneratorReader {
+  words: Box<dyn Iterator<Item = String> + Send>,
+  pending: Vec<u8>,
+  pos: usize,
+}
+
+impl GeneratorReader {
+  pub fn new(generator: &Generator) -> GeneratorReader {
+    GeneratorReader { words: generator.words(), pending: Default::default(), pos: 0 }
+  }
+}
+
+impl Read for GeneratorReader {
+  fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
+    if self.pos >= self.pending.len() {
+      match self.words.next() {
+        Some(word) => {
+          self.pending.clear();
+          self.pending.extend(word.as_bytes());
+          self.pending.push(b'\n');
+          self.pos = 0;
+        },
+        None => { return Ok(0); }
+      }
+    }
+
+    let n = buf.len().min(self.pending.len() - self.pos);
+    buf[0..n].copy_from_slice(&self.pending[self.pos..self.pos+n]);
+    self.pos += n;
+
+    Ok(n)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /**Function parses a generator and collects every word it produces. */
+  fn words(spec: &str) -> Vec<String> {
+    Generator::parse(spec).unwrap().unwrap().words().collect()
+  }
+
+  #[test]
+  fn range_steps_and_pads() {
+    assert_eq!(words("range:1-5"), vec!["1", "2", "3", "4", "5"]);
+    assert_eq!(words("range:0-25:step10:pad3"), vec!["000", "010", "020"]);
+    assert_eq!(words("range:10-1:step4"), vec!["10", "6", "2"]);
+    assert_eq!(words("range:98-101:pad2"), vec!["98", "99", "100", "101"]);
+    assert_eq!(Generator::parse("range:0-25:step10").unwrap().unwrap().count(), Some(3));
+    assert_eq!(Generator::parse("range:10-1:step4").unwrap().unwrap().count(), Some(3));
+
+    assert_eq!(Generator::parse("range:1-5:step0"), Err("invalid step step0".to_owned()));
+    assert_eq!(Generator::parse("range:1-5:padx"), Err("invalid padding padx".to_owned()));
+    assert_eq!(Generator::parse("range:1-5:width3"), Err("unknown range option width3".to_owned()));
+    assert_eq!(Generator::parse("range:5"), Err("range:5 expects START-END".to_owned()));
+  }
+
+  #[test]
+  fn charset_counts_like_an_odometer() {
+    assert_eq!(words("charset:ab:1-2"), vec!["a", "b", "aa", "ab", "ba", "bb"]);
+    assert_eq!(words("charset:xyx:2"), vec!["xx", "xy", "yx", "yy"]);
+    assert_eq!(words("charset:digits:0-1").len(), 10);
+
+    let generator = Generator::parse("charset:hex:1-3").unwrap().unwrap();
+    let all: Vec<String> = generator.words().collect();
+    assert_eq!(generator.count(), Some(16 + 256 + 4096));
+    assert_eq!(all.len(), 16 + 256 + 4096);
+    assert_eq!(all.last().unwrap(), "fff");
+
+    assert_eq!(Generator::parse("charset:ab:3-1"), Err("invalid range 3-1".to_owned()));
+    assert_eq!(Generator::parse("charset:ab:0"), Err("charset:ab:0 does not generate any words".to_owned()));
+  }
+
+  #[test]
+  fn date_steps_one_day_in_the_format() {
+    assert_eq!(words("date:2024-02-27:2024-03-01"), vec!["2024-02-27", "2024-02-28", "2024-02-29", "2024-03-01"]);
+    assert_eq!(words("date:2023-12-31:2024-01-01:%Y%m%d"), vec!["20231231", "20240101"]);
+    assert_eq!(words("date:2024-01-05:2024-01-05:%d/%m %a"), vec!["05/01 Fri"]);
+
+    assert_eq!(Generator::parse("date:2024-01-01:2024-01-01:%Q"), Err("invalid date format %Q".to_owned()));
+    assert_eq!(Generator::parse("date:2024-01-01:2024-01-01:%Y %H:%M"), Err("invalid date format %Y %H:%M".to_owned()));
+    assert!(Generator::parse("date:2024-02-01:2024-01-01").is_err());
+    assert!(Generator::parse("date:2024-13-01:2024-12-01").is_err());
+  }
+
+  #[test]
+  fn uuids_are_random_version_4() {
+    let all = words("uuid:3");
+
+    assert_eq!(all.len(), 3);
+    assert_ne!(all[0], all[1]);
+    assert!(all.iter().all(|u| u.len() == 36 && u.as_bytes()[14] == b'4'));
+  }
+
+  #[test]
+  fn generators_are_limited() {
+    assert!(Generator::parse("range:1-100000000").unwrap().is_some());
+    assert_eq!(
+      Generator::parse("range:0-100000000"),
+      Err(format!("range:0-100000000 generates 100000001 words, more than the limit of {}", MAX_GENERATED))
+    );
+    assert_eq!(
+      Generator::parse("charset:alnum:40"),
+      Err(format!("charset:alnum:40 generates more words than the limit of {}", MAX_GENERATED))
+    );
+    assert!(Generator::parse("uuid:100000001").is_err());
+  }
+
+  #[test]
+  fn other_arguments_are_not_generators() {
+    assert_eq!(Generator::parse("/usr/share/wordlists/common.txt"), Ok(None));
+    assert_eq!(Generator::parse("C:\\wordlists\\common.txt"), Ok(None));
+  }
+
+  #[test]
+  fn reader_writes_one_word_per_line() {
+    let mut reader = GeneratorReader::new(&Generator::parse("range:8-11").unwrap().unwrap());
+    let mut out = String::new();
+
+    reader.read_to_string(&mut out).unwrap();
+    assert_eq!(out, "8\n9\n10\n11\n");
+  }
+}
@@ -0,0 +1,544 @@
+// Module converts curl command lines and HAR entries into raw requests so they can be used as templates.
+// Copy a request from the browser with "Copy as cURL (bash)" or "Save all as HAR" and add {!} where the
+// payload should go.
+use std::fs;
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde_json::Value;
+
+use super::encoders::url_encode;
+use super::request::{FUZZ_MARKER, RawRequest, parse_header};
+
+// curl options that take a value but do not change the request.
+const CURL_IGNORED_VALUES: [&str; 24] = [
+  "-o", "--output", "-m", "--max-time", "--connect-timeout", "-w", "--write-out", "--retry", "-c", "--cookie-jar",
+  "-D", "--dump-header", "--cacert", "--cert", "--key", "-E", "--resolve", "--limit-rate", "-U", "--proxy-user",
+  "--max-redirs", "--retry-delay", "--interface", "--trace",
+];
+
+// Short curl options that take a value, the value can be attached as in -XPOST.
+const CURL_SHORT_VALUES: &str = "XHdbuAexomwcDEUFT";
+
+// curl options without a value that do not change the request.
+const CURL_IGNORED_FLAGS: [&str; 30] = [
+  "-s", "--silent", "-S", "--show-error", "-v", "--verbose", "-i", "--include", "--compressed", "-g", "--globoff",
+  "-f", "--fail", "-N", "--no-buffer", "-#", "--progress-bar", "-O", "--remote-name", "--http1.0", "--http1.1",
+  "--http2", "--http2-prior-knowledge", "--http3", "-0", "-1", "-2", "-3", "--tlsv1.2", "--tlsv1.3",
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CurlCommand {
+  pub request: Option<RawRequest>,
+  pub insecure: bool,                   // -k
+  pub follow_redirects: bool,           // -L
+  pub proxy: Option<String>,            // -x
+  pub cookie_file: Option<String>,      // -b with a file name
+}
+
+/**Function splits a shell command into words, handling quotes, escapes, $'...' strings and line continuations.
+ * Params:
+ *  command: &str {The command.}
+ * Returns Result<Vec<String>, String>
+ */
+pub fn shell_words(command: &str) -> Result<Vec<String>, String> {
+  let mut words: Vec<String> = Default::default();
+  let mut word = String::new();
+  let mut in_word = false;
+  let mut chars = command.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '\'' => {
+        in_word = true;
+        loop {
+          match chars.next() {
+            Some('\'') => { break; },
+            Some(ch) => { word.push(ch); },
+            None => { return Err("unterminated ' quote".to_owned()); }
+          }
+        }
+      },
+      '$' if chars.peek() == Some(&'\'') => {
+        chars.next();
+        in_word = true;
+
+        // \xHH escapes are bytes, so the quoted text is collected as bytes and decoded once it ends.
+        let mut bytes: Vec<u8> = Default::default();
+        let push_char = |bytes: &mut Vec<u8>, ch: char| {
+          bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+        };
+
+        loop {
+          match chars.next() {
+            Some('\'') => { break; },
+            Some('\\') => {
+              match chars.next() {
+                Some('n') => { bytes.push(b'\n'); },
+                Some('r') => { bytes.push(b'\r'); },
+                Some('t') => { bytes.push(b'\t'); },
+                Some('a') => { bytes.push(0x07); },
+                Some('b') => { bytes.push(0x08); },
+                Some('e' | 'E') => { bytes.push(0x1b); },
+                Some('f') => { bytes.push(0x0c); },
+                Some('v') => { bytes.push(0x0b); },
+                Some(kind @ ('x' | 'u' | 'U')) => {
+                  let max_digits = match kind { 'x' => 2, 'u' => 4, _ => 8 };
+                  let mut hex = String::new();
+                  while hex.len() < max_digits && chars.peek().map(|h| h.is_ascii_hexdigit()).unwrap_or(false) {
+                    hex.extend(chars.next());
+                  }
+
+                  let code = match u32::from_str_radix(hex.as_str(), 16) {
+                    Ok(c) => c,
+                    Err(_) => { return Err(format!("invalid escape \\{}{}", kind, hex)); }
+                  };
+
+                  match (kind, char::from_u32(code)) {
+                    ('x', _) => { bytes.push(code as u8); },
+                    (_, Some(ch)) => { push_char(&mut bytes, ch); },
+                    (_, None) => { return Err(format!("invalid escape \\{}{}", kind, hex)); }
+                  }
+                },
+                Some(ch) => { push_char(&mut bytes, ch); },
+                None => { return Err("unterminated $' quote".to_owned()); }
+              }
+            },
+            Some(ch) => { push_char(&mut bytes, ch); },
+            None => { return Err("unterminated $' quote".to_owned()); }
+          }
+        }
+
+        word.push_str(String::from_utf8_lossy(&bytes).as_ref());
+      },
+      '"' => {
+        in_word = true;
+        loop {
+          match chars.next() {
+            Some('"') => { break; },
+            Some('\\') => {
+              match chars.next() {
+                Some(ch @ ('"' | '\\' | '$' | '`')) => { word.push(ch); },
+                Some('\n') => {},
+                Some(ch) => { word.push('\\'); word.push(ch); },
+                None => { return Err("unterminated \" quote".to_owned()); }
+              }
+            },
+            Some(ch) => { word.push(ch); },
+            None => { return Err("unterminated \" quote".to_owned()); }
+          }
+        }
+      },
+      '\\' => {
+        match chars.next() {
+          Some('\n') => {},
+          Some('\r') if chars.peek() == Some(&'\n') => { chars.next(); },
+          Some(ch) => { word.push(ch); in_word = true; },
+          None => {}
+        }
+      },
+      c if c.is_whitespace() => {
+        if in_word {
+          words.push(std::mem::take(&mut word));
+          in_word = false;
+        }
+      },
+      _ => {
+        word.push(c);
+        in_word = true;
+      }
+    }
+  }
+
+  if in_word {
+    words.push(word);
+  }
+
+  Ok(words)
+}
+
+/**Function splits an argument into curl options. Short flags can be combined as in -sSL, and the first short
+ * option that takes a value uses the rest of the argument, as in -sXPOST. Long options can be written as --name=value.
+ * Params:
+ *  arg: &str {The argument.}
+ * Returns Vec<(String, Option<String>)> with each option and its attached value.
+ */
+fn split_option(arg: &str) -> Vec<(String, Option<String>)> {
+  if let Some(long) = arg.strip_prefix("--") {
+    return match long.split_once('=') {
+      Some((n, v)) => vec![(format!("--{}", n), Some(v.to_owned()))],
+      None => vec![(arg.to_owned(), None)],
+    };
+  }
+
+  let short = match arg.strip_prefix('-') {
+    Some(s) if !s.is_empty() => s,
+    _ => { return vec![(arg.to_owned(), None)]; }
+  };
+
+  let mut options: Vec<(String, Option<String>)> = Default::default();
+  for (i, c) in short.char_indices() {
+    let rest = &short[i + c.len_utf8()..];
+
+    if CURL_SHORT_VALUES.contains(c) && !rest.is_empty() {
+      options.push((format!("-{}", c), Some(rest.to_owned())));
+      break;
+    }
+
+    options.push((format!("-{}", c), None));
+    if CURL_SHORT_VALUES.contains(c) {
+      break;
+    }
+  }
+
+  options
+}
+
+/**Function returns the data for -d and --data-binary, reading it from a file when it starts with @.
+ * Params:
+ *  value:          String {The option value.}
+ *  strip_newlines: bool   {Removes carriage returns and newlines from the file like curl does for -d.}
+ * Returns Result<String, String>
+ */
+fn read_data(value: String, strip_newlines: bool) -> Result<String, String> {
+  let file_name = match value.strip_prefix('@') {
+    Some(f) => f,
+    None => { return Ok(value); }
+  };
+
+  if file_name == "-" {
+    return Err("reading curl data from stdin with @- is not supported".to_owned());
+  }
+
+  match fs::read(file_name) {
+    Ok(bytes) => {
+      let text = String::from_utf8_lossy(&bytes);
+      match strip_newlines {
+        true => Ok(text.chars().filter(|c| *c != '\r' && *c != '\n').collect()),
+        false => Ok(text.into_owned()),
+      }
+    },
+    Err(e) => Err(format!("unable to read curl data {} - {}", file_name, e)),
+  }
+}
+
+/**Function converts a curl command into a request.
+ * Params:
+ *  command: &str {The curl command line.}
+ * Returns Result<CurlCommand, String>
+ */
+pub fn parse_curl(command: &str) -> Result<CurlCommand, String> {
+  let words = shell_words(command)?;
+  let mut args = words.iter().map(|w| w.as_str()).peekable();
+
+  match args.next() {
+    Some(c) if c == "curl" || c.ends_with("/curl") || c.ends_with("curl.exe") => {},
+    _ => { return Err("the command does not start with curl".to_owned()); }
+  }
+
+  let mut out = CurlCommand::default();
+  let mut method: Option<String> = None;
+  let mut url: Option<String> = None;
+  let mut headers: Vec<(String, String)> = Default::default();
+  let mut data: Vec<String> = Default::default();
+  let mut get = false;
+  let mut head = false;
+
+  while let Some(arg) = args.next() {
+    for (name, attached) in split_option(arg) {
+      let mut value = || -> Result<String, String> {
+        match attached.clone() {
+          Some(v) => Ok(v),
+          None => args.next().map(|v| v.to_owned()).ok_or_else(|| format!("{} expects a value", name)),
+        }
+      };
+
+      match name.as_str() {
+        "-X" | "--request" => { method = Some(value()?); },
+        "-H" | "--header" => {
+          let h = value()?;
+          if h.trim_end().ends_with(';') && !h.contains(':') {
+            headers.push((h.trim_end().trim_end_matches(';').to_owned(), String::new()));   // curl sends "Name;" as an empty header.
+          }
+          else {
+            headers.push(parse_header(h.as_str())?);
+          }
+        },
+        "-d" | "--data" | "--data-ascii" => { data.push(read_data(value()?, true)?); },
+        "--data-binary" => { data.push(read_data(value()?, false)?); },
+        "--data-raw" => { data.push(value()?); },
+        "--data-urlencode" => {
+          let v = value()?;
+          if v.contains(FUZZ_MARKER) {
+            return Err(format!("{} in {} would be url encoded before it is replaced, use --data-raw and --encode url instead", FUZZ_MARKER, name));
+          }
+
+          data.push(match v.split_once('=') {
+            Some((n, text)) => format!("{}={}", n, url_encode(text)),
+            None => url_encode(v.as_str()),
+          });
+        },
+        "--json" => {
+          data.push(value()?);
+          headers.push(("Content-Type".to_owned(), "application/json".to_owned()));
+          headers.push(("Accept".to_owned(), "application/json".to_owned()));
+        },
+        "-b" | "--cookie" => {
+          let c = value()?;
+          match c.contains('=') {
+            true => { headers.push(("Cookie".to_owned(), c)); },
+            false => { out.cookie_file = Some(c); }
+          }
+        },
+        "-u" | "--user" => {
+          let v = value()?;
+          if v.contains(FUZZ_MARKER) {
+            return Err(format!(
+              "{} in {} would be base64 encoded before it is replaced, use -H \"Authorization: Basic {}\" and --encode prefix:USER:,base64 instead",
+              FUZZ_MARKER, name, FUZZ_MARKER
+            ));
+          }
+
+          headers.push(("Authorization".to_owned(), format!("Basic {}", BASE64.encode(v))));
+        },
+        "-A" | "--user-agent" => { headers.push(("User-Agent".to_owned(), value()?)); },
+        "-e" | "--referer" => { headers.push(("Referer".to_owned(), value()?)); },
+        "-x" | "--proxy" => { out.proxy = Some(value()?); },
+        "--url" => { url = Some(value()?); },
+        "-G" | "--get" => { get = true; },
+        "-I" | "--head" => { head = true; },
+        "-k" | "--insecure" => { out.insecure = true; },
+        "-L" | "--location" => { out.follow_redirects = true; },
+        "-F" | "--form" | "-T" | "--upload-file" => {
+          return Err(format!("{} is not supported, use --request with a raw request instead", name));
+        },
+        n if CURL_IGNORED_VALUES.contains(&n) => { value()?; },
+        n if CURL_IGNORED_FLAGS.contains(&n) => {},
+        n if n.starts_with('-') && n.len() > 1 => { return Err(format!("unsupported curl option {}", n)); },
+        _ => { url = Some(arg.to_owned()); }
+      }
+    }
+  }
+
+  let mut url = match url {
+    Some(u) => u,
+    None => { return Err("the curl command does not have a url".to_owned()); }
+  };
+
+  let mut body = match data.len() {
+    0 => None,
+    _ => Some(data.join("&")),
+  };
+
+  // -G moves the data into the query string.
+  if get {
+    if let Some(b) = body.take() {
+      let joiner = if url.contains('?') { '&' } else { '?' };
+      url = format!("{}{}{}", url, joiner, b);
+    }
+  }
+
+  let method = match (method, head, body.is_some()) {
+    (Some(m), _, _) => m,
+    (None, true, _) => "HEAD".to_owned(),
+    (None, false, true) => "POST".to_owned(),
+    (None, false, false) => "GET".to_owned(),
+  };
+
+  if body.is_some() && !headers.iter().any(|(n, _)| n.eq_ignore_ascii_case("Content-Type")) {
+    headers.push(("Content-Type".to_owned(), "application/x-www-form-urlencoded".to_owned()));
+  }
+
+  out.request = Some(RawRequest { method, target: url, headers, body });
+  Ok(out)
+}
+
+/**Function loads a curl command from a file, or uses the argument as the command.
+ * Params:
+ *  spec: &str {A file containing the command, or the command itself.}
+ * Returns Result<CurlCommand, String>
+ */
+pub fn load_curl(spec: &str) -> Result<CurlCommand, String> {
+  let command = match fs::metadata(spec).map(|m| m.is_file()).unwrap_or(false) {
+    true => {
+      match fs::read_to_string(spec) {
+        Ok(c) => c,
+        Err(e) => { return Err(format!("unable to read curl command {} - {}", spec, e)); }
+      }
+    },
+    false => spec.to_owned(),
+  };
+
+  parse_curl(command.trim())
+}
+
+/**Function reads the requests from a HAR file and returns the selected entry.
+ * Params:
+ *  file_name: &str          {The HAR file exported from the browser.}
+ *  entry:     Option<&str>  {The entry number starting at 1, or text that appears in its url.}
+ * Returns Result<RawRequest, String>
+ */
+pub fn load_har(file_name: &str, entry: Option<&str>) -> Result<RawRequest, String> {
+  let text = match fs::read_to_string(file_name) {
+    Ok(t) => t,
+    Err(e) => { return Err(format!("unable to read HAR file {} - {}", file_name, e)); }
+  };
+
+  let har: Value = match serde_json::from_str(text.as_str()) {
+    Ok(v) => v,
+    Err(e) => { return Err(format!("invalid HAR file {} - {}", file_name, e)); }
+  };
+
+  let requests: Vec<&Value> = match har["log"]["entries"].as_array() {
+    Some(entries) => entries.iter().map(|e| &e["request"]).filter(|r| r["url"].is_string()).collect(),
+    None => { return Err(format!("{} does not contain any entries", file_name)); }
+  };
+
+  let describe = |i: usize, r: &Value| format!("  {}: {} {}", i+1, r["method"].as_str().unwrap_or("GET"), r["url"].as_str().unwrap_or(""));
+
+  let selected = match entry {
+    Some(e) => {
+      match e.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= requests.len() => requests[n-1],
+        Ok(n) => { return Err(format!("{} only has {} entries, entry {} does not exist", file_name, requests.len(), n)); },
+        Err(_) => {
+          match requests.iter().find(|r| r["url"].as_str().unwrap_or("").contains(e)) {
+            Some(r) => r,
+            None => { return Err(format!("no entry in {} has a url containing {}", file_name, e)); }
+          }
+        }
+      }
+    },
+    None if requests.len() == 1 => requests[0],
+    None => {
+      let list: Vec<String> = requests.iter().enumerate().take(50).map(|(i, r)| describe(i, r)).collect();
+      return Err(format!(
+        "{} has {} entries, select one with --har-entry <N|TEXT>:\n{}", file_name, requests.len(), list.join("\n")
+      ));
+    }
+  };
+
+  let mut headers: Vec<(String, String)> = Default::default();
+  for h in selected["headers"].as_array().map(|a| a.as_slice()).unwrap_or(&[]) {
+    let name = h["name"].as_str().unwrap_or("");
+    let value = h["value"].as_str().unwrap_or("");
+
+    // HTTP/2 pseudo headers and lengths are recreated for every request.
+    if name.starts_with(':') || name.eq_ignore_ascii_case("content-length") || name.is_empty() {
+      continue;
+    }
+
+    headers.push((name.to_owned(), value.to_owned()));
+  }
+
+  let body = selected["postData"]["text"].as_str().filter(|b| !b.is_empty()).map(|b| b.to_owned());
+
+  if let (Some(_), Some(mime)) = (&body, selected["postData"]["mimeType"].as_str()) {
+    if !headers.iter().any(|(n, _)| n.eq_ignore_ascii_case("Content-Type")) {
+      headers.push(("Content-Type".to_owned(), mime.to_owned()));
+    }
+  }
+
+  Ok(RawRequest {
+    method: selected["method"].as_str().unwrap_or("GET").to_owned(),
+    target: selected["url"].as_str().unwrap_or("").to_owned(),
+    headers,
+    body,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::arguments::test_files::temp_file;
+
+  fn request(command: &str) -> RawRequest {
+    parse_curl(command).unwrap().request.unwrap()
+  }
+
+  #[test]
+  fn shell_words_handles_quotes_and_continuations() {
+    let words = shell_words("curl 'http://a/x y' -H \"X-A: \\\"b\\\" \\$c\" \\\n  -d a\\ b").unwrap();
+
+    assert_eq!(words, vec!["curl", "http://a/x y", "-H", "X-A: \"b\" $c", "-d", "a b"]);
+    assert_eq!(shell_words("a''b \"\"").unwrap(), vec!["ab", ""]);
+    assert!(shell_words("curl 'open").is_err());
+    assert!(shell_words("curl \"open").is_err());
+  }
+
+  #[test]
+  fn dollar_quotes_decode_escapes() {
+    assert_eq!(shell_words("$'a\\tb\\n\\'c'").unwrap(), vec!["a\tb\n'c"]);
+    assert_eq!(shell_words("$'\\u00e9\\U0001F980\\x41'").unwrap(), vec!["é🦀A"]);
+    assert_eq!(shell_words("$'\\xc3\\xa9'").unwrap(), vec!["é"]);                 // UTF-8 written as bytes.
+    assert_eq!(shell_words("$'\\xff'").unwrap(), vec!["\u{fffd}"]);
+    assert!(shell_words("$'\\xzz'").is_err());
+    assert!(shell_words("$'\\uD800'").is_err());
+  }
+
+  #[test]
+  fn combined_short_flags_are_split() {
+    let curl = parse_curl("curl -sSkL -XPUT -H'X-A: 1' https://a/{!}").unwrap();
+    let req = curl.request.unwrap();
+
+    assert!(curl.insecure && curl.follow_redirects);
+    assert_eq!(req.method, "PUT");
+    assert_eq!(req.target, "https://a/{!}");
+    assert_eq!(req.headers, vec![("X-A".to_owned(), "1".to_owned())]);
+
+    assert_eq!(request("curl -sXDELETE https://a/").method, "DELETE");
+    assert_eq!(request("curl -sX PATCH https://a/").method, "PATCH");
+    assert_eq!(parse_curl("curl -kx http://p:8080 https://a/").unwrap().proxy.unwrap(), "http://p:8080");
+    assert!(parse_curl("curl -sé https://a/").is_err());
+    assert!(parse_curl("curl -sZ https://a/").is_err());
+  }
+
+  #[test]
+  fn encoded_options_keep_their_values_without_the_marker() {
+    assert_eq!(request("curl https://a/ --data-urlencode 'q=a b&c' --data-urlencode x/y").body.unwrap(), "q=a%20b%26c&x%2Fy");
+    assert_eq!(request("curl https://a/ -u admin:pw").headers, vec![("Authorization".to_owned(), "Basic YWRtaW46cHc=".to_owned())]);
+
+    assert!(parse_curl("curl https://a/ --data-urlencode 'q={!}'").unwrap_err().contains("--data-raw and --encode url"));
+    assert!(parse_curl("curl https://a/ -u 'admin:{!}'").unwrap_err().contains("--encode prefix:USER:,base64"));
+  }
+
+  #[test]
+  fn data_is_read_from_files() {
+    let file = temp_file("curl_data", "a=1\r\nb=2\n");
+
+    let req = request(format!("curl https://a/ -d @{}", file).as_str());
+    assert_eq!(req.method, "POST");
+    assert_eq!(req.body.unwrap(), "a=1b=2");
+
+    assert_eq!(request(format!("curl https://a/ --data-binary @{}", file).as_str()).body.unwrap(), "a=1\r\nb=2\n");
+    assert_eq!(request("curl https://a/ --data-raw @x -d c=3").body.unwrap(), "@x&c=3");
+    assert!(parse_curl("curl https://a/ -d @/nonexistent/soda").is_err());
+
+    let _ = fs::remove_file(file);
+  }
+
+  #[test]
+  fn har_entries_are_selected_by_number_or_url() {
+    let har = temp_file("har_entries", r#"{"log": {"entries": [
+      {"request": {"method": "GET", "url": "https://a/one", "headers": [{"name": ":authority", "value": "a"}]}},
+      {"request": {"method": "POST", "url": "https://a/login", "headers": [{"name": "Content-Length", "value": "3"},
+        {"name": "X-A", "value": "1"}], "postData": {"mimeType": "application/json", "text": "{}"}}}
+    ]}}"#);
+
+    let first = load_har(har.as_str(), Some("1")).unwrap();
+    assert_eq!((first.method.as_str(), first.target.as_str()), ("GET", "https://a/one"));
+    assert!(first.headers.is_empty());
+
+    let login = load_har(har.as_str(), Some("login")).unwrap();
+    assert_eq!(login.method, "POST");
+    assert_eq!(login.body.unwrap(), "{}");
+    assert_eq!(login.headers, vec![
+      ("X-A".to_owned(), "1".to_owned()), ("Content-Type".to_owned(), "application/json".to_owned())
+    ]);
+
+    assert!(load_har(har.as_str(), None).unwrap_err().contains("2: POST https://a/login"));
+    assert!(load_har(har.as_str(), Some("3")).is_err());
+    assert!(load_har(har.as_str(), Some("missing")).is_err());
+
+    let _ = fs::remove_file(har);
+  }
+}
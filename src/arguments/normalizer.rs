@@ -0,0 +1,214 @@
+// Module cleans up wordlists before they are turned into requests. It removes byte order marks, converts
+// UTF-16 wordlists to UTF-8 and drops comments, blank lines and duplicate words.
+use std::{
+  io::{BufRead, BufReader, Read, Error as IoError},
+  collections::VecDeque,
+};
+
+pub const COMMENT: &str = "#";    // Lines starting with this are treated as comments.
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: [u8; 2] = [0xFF, 0xFE];
+const UTF16_BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextEncoding {
+  Utf8,
+  Utf16Le,
+  Utf16Be,
+}
+
+impl TextEncoding {
+
+  /**Function detects the encoding of a wordlist from its byte order mark. Without a mark, UTF-16 is
+   * assumed when every other byte at the start of the text is zero.
+   * Params:
+   *  start: &[u8] {The first bytes of the wordlist.}
+   * Returns (TextEncoding, usize) {The encoding and the length of the byte order mark.}
+   */
+  pub fn detect(start: &[u8]) -> (TextEncoding, usize) {
+    if start.starts_with(&UTF8_BOM) {
+      return (TextEncoding::Utf8, UTF8_BOM.len());
+    }
+    if start.starts_with(&UTF16_LE_BOM) {
+      return (TextEncoding::Utf16Le, UTF16_LE_BOM.len());
+    }
+    if start.starts_with(&UTF16_BE_BOM) {
+      return (TextEncoding::Utf16Be, UTF16_BE_BOM.len());
+    }
+
+    let pairs: Vec<&[u8]> = start.chunks_exact(2).take(32).collect();
+    if pairs.len() >= 2 {
+      if pairs.iter().all(|p| p[0] != 0 && p[1] == 0) {
+        return (TextEncoding::Utf16Le, 0);
+      }
+      if pairs.iter().all(|p| p[0] == 0 && p[1] != 0) {
+        return (TextEncoding::Utf16Be, 0);
+      }
+    }
+
+    (TextEncoding::Utf8, 0)
+  }
+}
+
+/**Struct converts a UTF-16 stream into UTF-8 so the rest of the reader only deals with one encoding.
+ * Unpaired surrogates are replaced with U+FFFD. */
+pub struct Utf16Reader {
+  inner: Box<dyn BufRead>,
+  big_endian: bool,
+  carry: Option<u8>,              // The first byte of a unit split between two reads.
+  high_surrogate: Option<u16>,    // The first half of a surrogate pair split between two reads.
+  pending: VecDeque<u8>,          // UTF-8 bytes that have been decoded but not read yet.
+}
+
+impl Utf16Reader {
+  pub fn new(inner: Box<dyn BufRead>, big_endian: bool) -> Utf16Reader {
+    Utf16Reader { inner, big_endian, carry: None, high_surrogate: None, pending: Default::default() }
+  }
+
+  fn push_char(&mut self, ch: char) {
+    let mut encoded = [0u8; 4];
+    self.pending.extend(ch.encode_utf8(&mut encoded).as_bytes());
+  }
+
+  fn push_unit(&mut self, unit: u16) {
+    match (self.high_surrogate.take(), unit) {
+      (Some(high), 0xDC00..=0xDFFF) => {
+        let code = 0x10000 + (((high as u32) - 0xD800) << 10) + ((unit as u32) - 0xDC00);
+        self.push_char(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+      },
+      (high, 0xD800..=0xDBFF) => {
+        if high.is_some() { self.push_char(char::REPLACEMENT_CHARACTER); }
+        self.high_surrogate = Some(unit);
+      },
+      (high, _) => {
+        if high.is_some() { self.push_char(char::REPLACEMENT_CHARACTER); }
+        self.push_char(char::from_u32(unit as u32).unwrap_or(char::REPLACEMENT_CHARACTER));
+      }
+    }
+  }
+
+  /**Function decodes the next chunk of the inner reader. Returns false at the end of the stream. */
+  fn decode_chunk(&mut self) -> Result<bool, IoError> {
+    let bytes = self.inner.fill_buf()?.to_vec();
+
+    if bytes.is_empty() {
+      if self.carry.take().is_some() || self.high_surrogate.take().is_some() {
+        self.push_char(char::REPLACEMENT_CHARACTER);
+      }
+
+      return Ok(false);
+    }
+
+    self.inner.consume(bytes.len());
+
+    let mut units: Vec<u8> = Default::default();
+    if let Some(b) = self.carry.take() {
+      units.push(b);
+    }
+    units.extend(bytes);
+
+    if units.len() % 2 == 1 {
+      self.carry = units.pop();
+    }
+
+    for pair in units.chunks_exact(2) {
+      let unit = match self.big_endian {
+        true => u16::from_be_bytes([pair[0], pair[1]]),
+        false => u16::from_le_bytes([pair[0], pair[1]]),
+      };
+
+      self.push_unit(unit);
+    }
+
+    Ok(true)
+  }
+}
+
+impl Read for Utf16Reader {
+  fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
+    while self.pending.is_empty() {
+      if !self.decode_chunk()? && self.pending.is_empty() {
+        return Ok(0);
+      }
+    }
+
+    let n = buf.len().min(self.pending.len());
+    for (i, b) in self.pending.drain(0..n).enumerate() {
+      buf[i] = b;
+    }
+
+    Ok(n)
+  }
+}
+
+/**Function removes the byte order mark from a reader and converts UTF-16 text to UTF-8.
+ * Params:
+ *  reader: Box<dyn BufRead> {The decompressed wordlist.}
+ * Returns Result<Box<dyn BufRead>, IoError>
+ */
+pub fn decode_text(mut reader: Box<dyn BufRead>) -> Result<Box<dyn BufRead>, IoError> {
+  let (encoding, bom_len) = TextEncoding::detect(reader.fill_buf()?);
+  reader.consume(bom_len);
+
+  match encoding {
+    TextEncoding::Utf8 => { Ok(reader) },
+    TextEncoding::Utf16Le => { Ok(Box::new(BufReader::new(Utf16Reader::new(reader, false)))) },
+    TextEncoding::Utf16Be => { Ok(Box::new(BufReader::new(Utf16Reader::new(reader, true)))) }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NormalizeOptions {
+  pub trim: bool,             // Removes whitespace around each word.
+  pub skip_comments: bool,    // Drops lines starting with #.
+  pub dedup: bool,            // Drops words that have already been read, which keeps every word in memory.
+}
+
+impl Default for NormalizeOptions {
+  fn default() -> Self {
+    NormalizeOptions { trim: true, skip_comments: true, dedup: false }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DropStats {
+  pub blank: usize,
+  pub comments: usize,
+  pub duplicates: usize,
+}
+
+impl DropStats {
+  pub fn total(&self) -> usize {
+    self.blank + self.comments + self.duplicates
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Line<'a> {
+  Word(&'a str),
+  Blank,
+  Comment,
+}
+
+/**Function classifies a line from the wordlist, trimming it when requested.
+ * Params:
+ *  line:    &str              {The line without its new line delimiter.}
+ *  options: &NormalizeOptions {The normalizer settings.}
+ * Returns Line.
+ */
+pub fn classify<'a>(line: &'a str, options: &NormalizeOptions) -> Line<'a> {
+  let word = match options.trim {
+    true => line.trim(),
+    false => line,
+  };
+
+  if word.trim().is_empty() {
+    return Line::Blank;
+  }
+
+  if options.skip_comments && word.trim_start().starts_with(COMMENT) {
+    return Line::Comment;
+  }
+
+  Line::Word(word)
+}
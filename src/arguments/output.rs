@@ -0,0 +1,159 @@
+// Module owns the single thread that writes results to the console and the output file.
+use std::{
+  sync::mpsc::{self, Sender, Receiver},
+  thread,
+};
+
+use console::style;
+use reqwest::{StatusCode, Version};
+
+use super::arg_fmt;
+use super::fixed_buffer::U8FixedBuffer;
+
+#[derive(Debug)]
+pub enum OutputEvent {
+  Hit { request: String, status: StatusCode, len: u64, location: Option<String>, version: Option<Version> },        // Matches the filters, displayed and logged to the output file.
+  Response { request: String, status: StatusCode, len: u64, location: Option<String>, version: Option<Version> },   // Every response when running in verbose mode, displayed only.
+  Body(String),                                                  // The html body of a response.
+  Error(String),                                                 // Request or io errors.
+  Shutdown(Sender<()>),                                          // Flushes the output file and acknowledges on the sender.
+}
+
+#[derive(Debug, Clone)]
+pub struct SinkSettings {
+  pub debug: bool,
+  pub output: String,       // The output file name, empty when nothing should be logged.
+}
+
+/**Function formats a result line. The colour of the status code is determined by the status of the code.
+ * Good requests are displayed in green, bad requests are displayed in red and requests
+ * that are neither good nor bad are displayed in cyan. Redirects show where they point to.
+ * Params:
+ *  request:  &str             {The url request}
+ *  status:   StatusCode       {The status code of the request}
+ *  len:      u64              {The length of the response}
+ *  location: Option<&str>     {The Location header of a redirect}
+ *  version:  Option<Version>  {The HTTP version of the response when a protocol was selected}
+ * Returns String.
+ */
+pub fn format_result(request: &str, status: StatusCode, len: u64, location: Option<&str>, version: Option<Version>) -> String {
+  let code = match status {
+    StatusCode::OK | StatusCode::ACCEPTED => {
+      style(status).green().bright()
+    },
+
+    StatusCode::BAD_GATEWAY | StatusCode::BAD_REQUEST | StatusCode::EXPECTATION_FAILED | StatusCode::FAILED_DEPENDENCY |
+    StatusCode::HTTP_VERSION_NOT_SUPPORTED | StatusCode::NOT_FOUND | StatusCode::INTERNAL_SERVER_ERROR |
+    StatusCode::GATEWAY_TIMEOUT => {
+      style(status).red().bright()
+    },
+
+    _ => { style(status).cyan() }
+  };
+
+  let len = match version {
+    Some(v) => format!("{} -- {}", style(len).cyan(), style(format!("{:?}", v)).magenta()),
+    None => format!("{}", style(len).cyan()),
+  };
+
+  match location {
+    Some(l) => { format!("{request} -- {} -- {} -> {}", code, len, style(l).yellow()) },
+    None => { format!("{request} -- {} -- {}", code, len) }
+  }
+}
+
+/**Function starts the output thread. Workers send events through the returned sender and the thread
+ * is the only place that prints results or touches the output file, so lines are never interleaved.
+ * Params:
+ *  settings: SinkSettings {The debug flag and output file name.}
+ * Returns (Sender<OutputEvent>, JoinHandle<()>)
+ */
+pub fn start_sink(settings: SinkSettings) -> (Sender<OutputEvent>, thread::JoinHandle<()>) {
+  let (sender, recv) = mpsc::channel::<OutputEvent>();
+  let handle = thread::spawn(move || { run_sink(settings, recv); });
+
+  (sender, handle)
+}
+
+/**Function receives events until every sender has been dropped or a shutdown is requested.
+ * Params:
+ *  settings: SinkSettings           {The debug flag and output file name.}
+ *  recv:     Receiver<OutputEvent>  {The receiving end of the output channel.}
+ * Returns nothing.
+ */
+fn run_sink(settings: SinkSettings, recv: Receiver<OutputEvent>) {
+  let output = settings.output.as_str();
+  let mut u8_buffer = U8FixedBuffer::with_output(output);                // Stores data to be logged.
+
+  for event in recv.iter() {
+    match event {
+      OutputEvent::Hit { request, status, len, location, version } => {
+        println!("{}", format_result(request.as_str(), status, len, location.as_deref(), version));
+
+        if !output.is_empty() {
+          let status = match version {
+            Some(v) => format!("{} -- {:?}", status, v),
+            None => status.to_string(),
+          };
+
+          let line = match location {
+            Some(l) => format!("{} -- {} -> {}\n", request, status, l),
+            None => format!("{} -- {}\n", request, status),
+          };
+
+          if line.len() > u8_buffer.remaining() {                        // Buffer is emptied and written to disk.
+            flush_buffer(&mut u8_buffer, output, settings.debug);
+          }
+
+          match u8_buffer.push_str(line.as_str()) {
+            Ok(_) => {},
+            Err(e) => { arg_fmt::f_error("Failed to write data to file", "", format!("{}", e.kind())); }
+          }
+        }
+      },
+
+      OutputEvent::Response { request, status, len, location, version } => {
+        println!("{}", format_result(request.as_str(), status, len, location.as_deref(), version));
+      },
+
+      OutputEvent::Body(body) => {
+        println!("|\n|\n{}", body);
+      },
+
+      OutputEvent::Error(e) => {
+        println!("\n{}\n{}", style(e).red().bright(), style("__________________________________________________").cyan());
+      },
+
+      OutputEvent::Shutdown(ack) => {
+        flush_buffer(&mut u8_buffer, output, true);
+        let _ = ack.send(());
+        return;
+      }
+    }
+  }
+
+  // The contents of the u8 buffer is written to disk if there are left overs after the channel is closed.
+  flush_buffer(&mut u8_buffer, output, true);
+}
+
+/**Function writes the contents of the buffer to the output file and reports how many bytes were written.
+ * Params:
+ *  u8_buffer: &mut U8FixedBuffer {The buffer holding the logged lines.}
+ *  output:    &str               {The name of the output file.}
+ *  show:      bool               {Displays how many bytes were written.}
+ * Returns nothing.
+ */
+fn flush_buffer(u8_buffer: &mut U8FixedBuffer, output: &str, show: bool) {
+  if u8_buffer.is_empty() || output.is_empty() {
+    return;
+  }
+
+  match u8_buffer.flush_data() {
+    Ok(s) => {
+      if show {
+        arg_fmt::f_io(s, output);
+      }
+    },
+    Err(e) => { arg_fmt::f_error("Failed to write data to file", "", format!("{}", e.kind())); }
+  }
+}
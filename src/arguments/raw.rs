@@ -0,0 +1,504 @@
+// Module sends requests over a plain TCP or TLS socket so the bytes of the request file reach the server
+// exactly as they were written. The http client rewrites request lines, header names and line endings,
+// which hides request smuggling, malformed request lines and duplicate headers. Only {!} is replaced, so
+// a payload can also end a header or the request line, e.g. with words decoded by --encode unescape.
+use std::{
+  fs,
+  io::{self, Read, Write, Error as IoError, ErrorKind},
+  net::{SocketAddr, TcpStream, ToSocketAddrs},
+  time::Duration,
+};
+
+use native_tls::{TlsConnector, TlsStream};
+use reqwest::{StatusCode, Url, Version, header::{HeaderMap, HeaderName, HeaderValue}};
+
+use super::client::{self, ClientOptions};
+use super::request::{self, TestCase, FUZZ_MARKER};
+
+const MAX_HEAD_SIZE: usize = 64 * 1024;     // Responses with a larger status line and headers are rejected.
+const READ_SIZE: usize = 8 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct RawTemplate {
+  pub file_name: String,
+  bytes: Vec<u8>,                            // The request file with {!} left in place.
+  timeout: Duration,
+  tls: TlsConnector,
+  resolve: Vec<(String, Vec<SocketAddr>)>,   // Addresses from --resolve, the port comes from the url.
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawResponse {
+  pub status: StatusCode,
+  pub version: Option<Version>,
+  pub len: u64,                              // The length of the body after chunks are joined.
+  pub location: Option<String>,
+  pub body: Vec<u8>,
+}
+
+// A connection to the server, with or without TLS.
+enum RawStream {
+  Plain(TcpStream),
+  Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl Read for RawStream {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    match self {
+      RawStream::Plain(s) => s.read(buf),
+      RawStream::Tls(s) => s.read(buf),
+    }
+  }
+}
+
+impl Write for RawStream {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match self {
+      RawStream::Plain(s) => s.write(buf),
+      RawStream::Tls(s) => s.write(buf),
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    match self {
+      RawStream::Plain(s) => s.flush(),
+      RawStream::Tls(s) => s.flush(),
+    }
+  }
+}
+
+/**Function finds the first position of a byte pattern. */
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+  haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/**Function returns an error for a response that cannot be parsed. */
+fn invalid(message: String) -> IoError {
+  IoError::new(ErrorKind::InvalidData, message)
+}
+
+impl RawTemplate {
+
+  /**Function reads the request file and sets up the connections from the client options.
+   * Params:
+   *  file_name: &str           {The request file.}
+   *  options:   &ClientOptions {The timeout, TLS verification and --resolve entries.}
+   * Returns Result<RawTemplate, String>
+   */
+  pub fn load(file_name: &str, options: &ClientOptions) -> Result<RawTemplate, String> {
+    let bytes = match fs::read(file_name) {
+      Ok(b) => b,
+      Err(e) => { return Err(format!("unable to read raw request file {} - {}", file_name, e)); }
+    };
+
+    if find_bytes(&bytes, FUZZ_MARKER.as_bytes()).is_none() {
+      return Err(format!("{} does not contain {}, --raw only replaces {} in the request file", file_name, FUZZ_MARKER, FUZZ_MARKER));
+    }
+
+    let tls = match TlsConnector::builder()
+      .danger_accept_invalid_certs(options.insecure)
+      .danger_accept_invalid_hostnames(options.insecure)
+      .build() {
+      Ok(t) => t,
+      Err(e) => { return Err(format!("unable to set up TLS for raw requests - {}", e)); }
+    };
+
+    Ok(RawTemplate {
+      file_name: file_name.to_owned(),
+      bytes,
+      timeout: Duration::from_millis(options.timeout),
+      tls,
+      resolve: client::parse_resolve(&options.resolve)?,
+    })
+  }
+
+  /**Function checks if the request file contains the blank line that ends the headers. */
+  pub fn ends_headers(&self) -> bool {
+    find_bytes(&self.bytes, b"\r\n\r\n").is_some() || find_bytes(&self.bytes, b"\n\n").is_some()
+  }
+
+  /**Function returns the text shown for a test case.
+   * Params:
+   *  &self
+   *  case: &TestCase {The test case.}
+   * Returns String.
+   */
+  pub fn label(&self, case: &TestCase) -> String {
+    let label = format!("{} [{}]", case.url, case.payload.escape_debug());
+
+    match case.target.as_ref() {
+      Some(t) => format!("({}) {}", t, label),
+      None => label,
+    }
+  }
+
+  /**Function returns the request file with {!} replaced by the payload.
+   * Params:
+   *  &self
+   *  payload: &str {The payload.}
+   * Returns Vec<u8>
+   */
+  pub fn fill(&self, payload: &str) -> Vec<u8> {
+    let marker = FUZZ_MARKER.as_bytes();
+    let mut out: Vec<u8> = Vec::with_capacity(self.bytes.len() + payload.len());
+    let mut rest = self.bytes.as_slice();
+
+    while let Some(i) = find_bytes(rest, marker) {
+      out.extend_from_slice(&rest[..i]);
+      out.extend_from_slice(payload.as_bytes());
+      rest = &rest[i+marker.len()..];
+    }
+
+    out.extend_from_slice(rest);
+    out
+  }
+
+  /**Function opens a connection to the host of the url, over TLS for https urls.
+   * Params:
+   *  &self
+   *  url: &str {The url of the test case, only the scheme, host and port are used.}
+   * Returns Result<RawStream, IoError>
+   */
+  fn connect(&self, url: &str) -> Result<RawStream, IoError> {
+    let parsed = match Url::parse(url) {
+      Ok(u) => u,
+      Err(e) => { return Err(IoError::new(ErrorKind::InvalidInput, format!("invalid url {} - {}", url, e))); }
+    };
+
+    let host = parsed.host_str().unwrap_or("").trim_start_matches('[').trim_end_matches(']').to_owned();
+    let port = parsed.port_or_known_default().unwrap_or(80);
+
+    let addrs: Vec<SocketAddr> = match self.resolve.iter().find(|(h, _)| h.eq_ignore_ascii_case(host.as_str())) {
+      Some((_, resolved)) => resolved.iter().map(|a| SocketAddr::new(a.ip(), port)).collect(),
+      None => (host.as_str(), port).to_socket_addrs()?.collect(),
+    };
+
+    let mut last_error = IoError::new(ErrorKind::NotFound, format!("no addresses found for {}", host));
+    let mut tcp: Option<TcpStream> = None;
+
+    for addr in addrs {
+      match TcpStream::connect_timeout(&addr, self.timeout) {
+        Ok(s) => { tcp = Some(s); break; },
+        Err(e) => { last_error = e; }
+      }
+    }
+
+    let tcp = match tcp {
+      Some(s) => s,
+      None => { return Err(last_error); }
+    };
+
+    tcp.set_read_timeout(Some(self.timeout))?;
+    tcp.set_write_timeout(Some(self.timeout))?;
+
+    match parsed.scheme() {
+      "https" => {
+        match self.tls.connect(host.as_str(), tcp) {
+          Ok(s) => Ok(RawStream::Tls(Box::new(s))),
+          Err(e) => Err(IoError::other(format!("TLS handshake with {} failed - {}", host, e))),
+        }
+      },
+      _ => Ok(RawStream::Plain(tcp)),
+    }
+  }
+
+  /**Function sends the request file for a test case and reads the first response.
+   * Params:
+   *  &self
+   *  case: &TestCase {The url that selects the server and the payload.}
+   * Returns Result<RawResponse, IoError>
+   */
+  pub fn send(&self, case: &TestCase) -> Result<RawResponse, IoError> {
+    let request = self.fill(case.payload.as_str());
+    let mut stream = self.connect(case.url.as_str())?;
+
+    stream.write_all(&request)?;
+    stream.flush()?;
+
+    let no_body = request.starts_with(b"HEAD ");
+    read_response(&mut stream, no_body)
+  }
+}
+
+/**Function reads more data from the connection. A timeout is returned as an error, so a response that
+ * stops before its Content-Length, its last chunk or the server closing the connection is not reported
+ * as complete.
+ * Params:
+ *  stream: &mut R        {The connection.}
+ *  data:   &mut Vec<u8>  {The data read so far.}
+ *  what:   &str          {What was being read, used in the timeout error.}
+ * Returns Result<usize, IoError> {The number of bytes read, 0 when the server closed the connection.}
+ */
+fn read_more<R: Read>(stream: &mut R, data: &mut Vec<u8>, what: &str) -> Result<usize, IoError> {
+  let mut buf = [0u8; READ_SIZE];
+
+  match stream.read(&mut buf) {
+    Ok(n) => { data.extend_from_slice(&buf[..n]); Ok(n) },
+    Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+      Err(IoError::new(ErrorKind::TimedOut, format!("timed out waiting for {} after {} bytes", what, data.len())))
+    },
+    Err(e) if e.kind() == ErrorKind::Interrupted => read_more(stream, data, what),
+    Err(e) => Err(e),
+  }
+}
+
+/**Function finds the blank line that ends the status line and headers. Bare \n line endings are accepted
+ * as well, like most clients do.
+ * Params:
+ *  data: &[u8] {The data read so far.}
+ * Returns Option<(usize, usize)> {The length of the head and where the body starts.}
+ */
+fn head_end(data: &[u8]) -> Option<(usize, usize)> {
+  let crlf = find_bytes(data, b"\r\n\r\n").map(|i| (i, i + 4));
+  let lf = find_bytes(data, b"\n\n").map(|i| (i, i + 2));
+
+  match (crlf, lf) {
+    (Some(c), Some(l)) => Some(if l.0 < c.0 { l } else { c }),
+    (c, l) => c.or(l),
+  }
+}
+
+/**Function returns the length of a chunked body once the last chunk has arrived.
+ * Params:
+ *  body: &[u8] {The chunked body read so far.}
+ * Returns Option<Vec<u8>> {The body without the chunk sizes, None while chunks are missing.}
+ */
+fn dechunk(body: &[u8]) -> Option<Vec<u8>> {
+  let mut out: Vec<u8> = Default::default();
+  let mut rest = body;
+
+  loop {
+    let line_end = find_bytes(rest, b"\n")?;
+    let size_line = String::from_utf8_lossy(&rest[..line_end]);
+    let size_text = size_line.split(';').next().unwrap_or("").trim();
+    let size = usize::from_str_radix(size_text, 16).ok()?;
+    rest = &rest[line_end+1..];
+
+    if size == 0 {
+      // The trailers end with a blank line.
+      loop {
+        let line_end = find_bytes(rest, b"\n")?;
+        let blank = rest[..line_end].iter().all(|b| *b == b'\r');
+        rest = &rest[line_end+1..];
+
+        if blank {
+          return Some(out);
+        }
+      }
+    }
+
+    if rest.len() < size {
+      return None;
+    }
+
+    out.extend_from_slice(&rest[..size]);
+    rest = &rest[size..];
+
+    if rest.starts_with(b"\r\n") {
+      rest = &rest[2..];
+    }
+    else if rest.starts_with(b"\n") {
+      rest = &rest[1..];
+    }
+    else if rest.is_empty() || rest == b"\r" {
+      return None;
+    }
+  }
+}
+
+/**Function reads the status line, headers and body of the first final response, skipping 100 Continue
+ * and other informational responses. The body ends after Content-Length bytes, after the last chunk or
+ * when the server closes the connection.
+ * Params:
+ *  stream:  &mut R  {The connection.}
+ *  no_body: bool    {True for HEAD requests, which are answered without a body.}
+ * Returns Result<RawResponse, IoError>
+ */
+fn read_response<R: Read>(stream: &mut R, no_body: bool) -> Result<RawResponse, IoError> {
+  let mut data: Vec<u8> = Default::default();
+
+  loop {
+    let (head_len, body_start) = loop {
+      if let Some(end) = head_end(&data) {
+        break end;
+      }
+
+      if data.len() > MAX_HEAD_SIZE {
+        return Err(invalid(format!("response headers are larger than {} bytes", MAX_HEAD_SIZE)));
+      }
+
+      if read_more(stream, &mut data, "the response headers")? == 0 {
+        match data.len() {
+          0 => { return Err(IoError::new(ErrorKind::UnexpectedEof, "the server closed the connection without a response")); },
+          n => break (n, n),                                        // Only a status line and headers were sent.
+        }
+      }
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_len]).to_string();
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or("").trim();
+
+    let mut parts = status_line.split_whitespace();
+    let version = match parts.next() {
+      Some("HTTP/0.9") => Some(Version::HTTP_09),
+      Some("HTTP/1.0") => Some(Version::HTTP_10),
+      Some("HTTP/1.1") => Some(Version::HTTP_11),
+      Some(v) if v.starts_with("HTTP/") => None,
+      _ => { return Err(invalid(format!("invalid status line {:?}", status_line))); }
+    };
+
+    let status = match parts.next().and_then(|c| c.parse::<u16>().ok()).map(StatusCode::from_u16) {
+      Some(Ok(s)) => s,
+      _ => { return Err(invalid(format!("invalid status line {:?}", status_line))); }
+    };
+
+    // 100 Continue and 103 Early Hints come before the response to the request.
+    if status.is_informational() && status != StatusCode::SWITCHING_PROTOCOLS {
+      data.drain(..body_start);
+      continue;
+    }
+
+    let mut headers = HeaderMap::new();
+    for line in lines {
+      if let Some((name, value)) = line.split_once(':') {
+        if let (Ok(n), Ok(v)) = (HeaderName::from_bytes(name.trim().as_bytes()), HeaderValue::from_str(value.trim())) {
+          headers.append(n, v);
+        }
+      }
+    }
+
+    let content_length = headers.get("content-length").and_then(|v| v.to_str().ok()).and_then(|v| v.trim().parse::<usize>().ok());
+    let chunked = headers.get_all("transfer-encoding").iter()
+      .filter_map(|v| v.to_str().ok())
+      .any(|v| v.to_lowercase().contains("chunked"));
+
+    let empty = no_body || status.is_informational() || status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED;
+
+    let body = match (empty, chunked, content_length) {
+      (true, _, _) => Vec::new(),
+      (false, true, _) => {
+        loop {
+          if let Some(b) = dechunk(&data[body_start..]) {
+            break b;
+          }
+
+          if read_more(stream, &mut data, "the last chunk")? == 0 {
+            break data[body_start..].to_vec();                       // The chunks were cut off.
+          }
+        }
+      },
+      (false, false, Some(len)) => {
+        while data.len() - body_start < len {
+          if read_more(stream, &mut data, format!("{} body bytes", len).as_str())? == 0 {
+            break;
+          }
+        }
+
+        data[body_start..].iter().take(len).copied().collect()
+      },
+      (false, false, None) => {
+        // Without a length the body ends when the server closes the connection.
+        while read_more(stream, &mut data, "the server to close the connection")? > 0 {}
+        data[body_start..].to_vec()
+      }
+    };
+
+    return Ok(RawResponse {
+      status,
+      version,
+      len: body.len() as u64,
+      location: request::redirect_location(status, &headers),
+      body,
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::VecDeque;
+
+  // Returns the parts one read at a time, then reports a closed connection.
+  struct Script(VecDeque<Result<&'static [u8], ErrorKind>>);
+
+  impl Read for Script {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      match self.0.pop_front() {
+        Some(Ok(part)) => { buf[..part.len()].copy_from_slice(part); Ok(part.len()) },
+        Some(Err(kind)) => Err(IoError::from(kind)),
+        None => Ok(0),
+      }
+    }
+  }
+
+  fn read(parts: &[&'static [u8]]) -> Result<RawResponse, IoError> {
+    read_response(&mut Script(parts.iter().map(|p| Ok(*p)).collect()), false)
+  }
+
+  #[test]
+  fn content_length_ends_the_body() {
+    let r = read(&[b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhel", b"lo", b"HTTP/1.1 500 Next\r\n\r\n"]).unwrap();
+
+    assert_eq!((r.status, r.version, r.body.as_slice(), r.len), (StatusCode::OK, Some(Version::HTTP_11), &b"hello"[..], 5));
+
+    let head = read_response(&mut Script(VecDeque::from([Ok(&b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n"[..])])), true).unwrap();
+    assert!(head.body.is_empty());
+  }
+
+  #[test]
+  fn chunks_are_joined_with_extensions_and_trailers() {
+    let r = read(&[
+      b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5;name=\"x\"\r\nhel", b"lo\r\n6\r\n world\r\n0\r\nX-Sum: 1\r\n", b"\r\n",
+    ]).unwrap();
+    assert_eq!(r.body, b"hello world");
+
+    assert_eq!(dechunk(b"3\nabc\n0\n\n").unwrap(), b"abc");
+    assert_eq!(dechunk(b"3\r\nabc\r\n0\r\n"), None);                     // The trailers have not ended.
+    assert_eq!(dechunk(b"3\r\nab"), None);
+    assert_eq!(dechunk(b"3\r\nabc"), None);
+  }
+
+  #[test]
+  fn body_without_length_ends_when_the_connection_closes() {
+    let r = read(&[b"HTTP/1.0 200 OK\r\nServer: x\r\n\r\none ", b"two"]).unwrap();
+    assert_eq!((r.version, r.body.as_slice()), (Some(Version::HTTP_10), &b"one two"[..]));
+
+    let r = read(&[b"HTTP/1.1 204 No Content\r\nX-A: 1"]).unwrap();
+    assert_eq!((r.status, r.len), (StatusCode::NO_CONTENT, 0));
+  }
+
+  #[test]
+  fn timeouts_before_the_end_of_the_response_are_errors() {
+    let timed_out = |parts: Vec<Result<&'static [u8], ErrorKind>>| {
+      read_response(&mut Script(parts.into()), false).unwrap_err().kind()
+    };
+
+    assert_eq!(timed_out(vec![Err(ErrorKind::WouldBlock)]), ErrorKind::TimedOut);
+    assert_eq!(timed_out(vec![Ok(b"HTTP/1.1 200 OK\r\nContent-Len"), Err(ErrorKind::TimedOut)]), ErrorKind::TimedOut);
+    assert_eq!(timed_out(vec![Ok(b"HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nabc"), Err(ErrorKind::WouldBlock)]), ErrorKind::TimedOut);
+    assert_eq!(timed_out(vec![Ok(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n"), Err(ErrorKind::WouldBlock)]), ErrorKind::TimedOut);
+    assert_eq!(timed_out(vec![Ok(b"HTTP/1.1 200 OK\r\n\r\nabc"), Err(ErrorKind::WouldBlock)]), ErrorKind::TimedOut);
+    assert_eq!(timed_out(vec![]), ErrorKind::UnexpectedEof);
+  }
+
+  #[test]
+  fn informational_responses_are_skipped() {
+    let r = read(&[b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 103 Early Hints\r\nLink: </a>\r\n\r\n", b"HTTP/1.1 302 Found\r\nLocation: /x\r\nContent-Length: 0\r\n\r\n"]).unwrap();
+    assert_eq!((r.status, r.location.as_deref()), (StatusCode::FOUND, Some("/x")));
+
+    let r = read(&[b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n"]).unwrap();
+    assert_eq!(r.status, StatusCode::SWITCHING_PROTOCOLS);
+  }
+
+  #[test]
+  fn heads_with_bare_line_feeds_are_read() {
+    let r = read(&[b"HTTP/1.1 404 Not Found\nContent-Length: 2\n\nno"]).unwrap();
+    assert_eq!((r.status, r.body.as_slice()), (StatusCode::NOT_FOUND, &b"no"[..]));
+
+    let r = read(&[b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\na\n\nb"]).unwrap();        // The body holds a blank line.
+    assert_eq!(r.body, b"a\n\nb");
+
+    assert!(read(&[b"SSH-2.0-OpenSSH\r\n\r\n"]).is_err());
+  }
+}
@@ -0,0 +1,391 @@
+// Module describes the request sent for each test case. The template holds the method, headers and body,
+// the test case holds the url and the payload that replaces {!} in the headers and body.
+use std::sync::Arc;
+
+use reqwest::{
+  Method, StatusCode, Version, Error,
+  header::{AUTHORIZATION, CONTENT_TYPE, COOKIE, LOCATION, HeaderMap},
+  blocking::{Client, Response},
+};
+
+use super::auth::Auth;
+use super::cookies::Cookies;
+use super::encoders::url_encode;
+
+pub const FUZZ_MARKER: &str = "{!}";          // Replaced with the payload.
+pub const CSRF_MARKER: &str = "{csrf}";       // Replaced with a fresh anti-forgery token.
+pub const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+// Headers from raw requests that are recalculated for every request.
+const DROPPED_RAW_HEADERS: [&str; 2] = ["content-length", "transfer-encoding"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+  pub url: String,          // The url with the payload in place.
+  pub payload: String,      // The payload, used for the headers and body.
+  pub target: Option<Arc<str>>, // The target from --targets, shown with the result.
+  pub word: usize,          // The position of the word in the wordlists, where a checkpoint resumes.
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestTemplate {
+  pub method: Method,
+  pub headers: Vec<(String, String)>,
+  pub body: Option<String>,
+  pub version: Option<Version>,         // The HTTP version of the request line, the client chooses when None.
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreparedRequest {
+  pub method: Method,
+  pub url: String,
+  pub headers: Vec<(String, String)>,
+  pub body: Option<String>,
+  pub version: Option<Version>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawRequest {
+  pub method: String,
+  pub target: String,                   // The path from the request line, or an absolute url.
+  pub headers: Vec<(String, String)>,
+  pub body: Option<String>,
+}
+
+impl Default for RequestTemplate {
+  fn default() -> Self {
+    RequestTemplate { method: Method::GET, headers: Default::default(), body: None, version: None }
+  }
+}
+
+/**Function parses a header such as "X-Api-Key: abc".
+ * Params:
+ *  header: &str {The header.}
+ * Returns Result<(String, String), String>
+ */
+pub fn parse_header(header: &str) -> Result<(String, String), String> {
+  match header.split_once(':') {
+    Some((name, value)) if !name.trim().is_empty() && !name.trim().contains(' ') => {
+      Ok((name.trim().to_owned(), value.trim().to_owned()))
+    },
+    _ => { Err(format!("invalid header {} - expected \"Name: value\"", header)) }
+  }
+}
+
+impl RequestTemplate {
+
+  /**Function creates the template from the command line options.
+   * Params:
+   *  method:  Option<&str> {The request method, GET by default.}
+   *  headers: &[String]    {Headers such as "Name: value".}
+   *  body:    Option<&str> {The request body.}
+   * Returns Result<RequestTemplate, String>
+   */
+  pub fn new(method: Option<&str>, headers: &[String], body: Option<&str>) -> Result<RequestTemplate, String> {
+    let method = match method {
+      Some(m) if m.contains(FUZZ_MARKER) => {
+        return Err(format!("invalid method {} - the payload can be placed in the url, headers and body, not the method", m));
+      },
+      Some(m) => {
+        match Method::from_bytes(m.to_uppercase().as_bytes()) {
+          Ok(m) => m,
+          Err(_) => { return Err(format!("invalid method {}", m)); }
+        }
+      },
+      None if body.is_some() => Method::POST,
+      None => Method::GET,
+    };
+
+    let mut parsed: Vec<(String, String)> = Default::default();
+    for h in headers {
+      parsed.push(parse_header(h.as_str())?);
+    }
+
+    // Bodies from the command line are usually forms, the same default curl uses.
+    if body.is_some() && !parsed.iter().any(|(n, _)| n.eq_ignore_ascii_case(CONTENT_TYPE.as_str())) {
+      parsed.push((CONTENT_TYPE.as_str().to_owned(), FORM_CONTENT_TYPE.to_owned()));
+    }
+
+    Ok(RequestTemplate { method, headers: parsed, body: body.map(|b| b.to_owned()), version: None })
+  }
+
+  /**Function checks if the payload is placed in the headers or body. */
+  pub fn fuzzes_request(&self) -> bool {
+    self.headers.iter().any(|(n, v)| n.contains(FUZZ_MARKER) || v.contains(FUZZ_MARKER)) ||
+    self.body.as_deref().map(|b| b.contains(FUZZ_MARKER)).unwrap_or(false)
+  }
+
+  /**Function checks if a request needs an anti-forgery token.
+   * Params:
+   *  &self
+   *  url: &str {The request url.}
+   * Returns bool.
+   */
+  pub fn uses_csrf(&self, url: &str) -> bool {
+    url.contains(CSRF_MARKER) ||
+    self.headers.iter().any(|(n, v)| n.contains(CSRF_MARKER) || v.contains(CSRF_MARKER)) ||
+    self.body.as_deref().map(|b| b.contains(CSRF_MARKER)).unwrap_or(false)
+  }
+
+  /**Function returns the text shown for a test case. The payload is added when it is not part of the url
+   * and results are tagged with their target when scanning more than one.
+   * Params:
+   *  &self
+   *  case: &TestCase {The test case.}
+   * Returns String.
+   */
+  pub fn label(&self, case: &TestCase) -> String {
+    let label = match self.fuzzes_request() {
+      true => format!("{} [{}]", case.url, case.payload),
+      false => case.url.clone(),
+    };
+
+    match case.target.as_ref() {
+      Some(t) => format!("({}) {}", t, label),
+      None => label,
+    }
+  }
+
+  /**Function checks if the body is sent as an url encoded form. */
+  pub fn is_form(&self) -> bool {
+    self.headers.iter().any(|(n, v)| n.eq_ignore_ascii_case(CONTENT_TYPE.as_str()) && v.trim().starts_with(FORM_CONTENT_TYPE))
+  }
+
+  /**Function fills in the template for a test case. The anti-forgery token is url encoded in the url and
+   * in form bodies, and placed as it is in the headers and other bodies.
+   * Params:
+   *  &self
+   *  case: &TestCase     {The url and payload.}
+   *  csrf: Option<&str>  {The anti-forgery token that replaces {csrf}.}
+   * Returns PreparedRequest.
+   */
+  pub fn prepare(&self, case: &TestCase, csrf: Option<&str>) -> PreparedRequest {
+    let encoded = csrf.map(url_encode);
+
+    let fill = |text: &str, token: Option<&str>| -> String {
+      let text = text.replace(FUZZ_MARKER, case.payload.as_str());
+      match token {
+        Some(t) => text.replace(CSRF_MARKER, t),
+        None => text,
+      }
+    };
+
+    let url = match encoded.as_deref() {
+      Some(token) => case.url.replace(CSRF_MARKER, token),
+      None => case.url.clone(),
+    };
+
+    let body_token = match self.is_form() {
+      true => encoded.as_deref(),
+      false => csrf,
+    };
+
+    PreparedRequest {
+      method: self.method.clone(),
+      url,
+      headers: self.headers.iter().map(|(n, v)| (fill(n, csrf), fill(v, csrf))).collect(),
+      body: self.body.as_deref().map(|b| fill(b, body_token)),
+      version: self.version,
+    }
+  }
+}
+
+// The blocking and async clients have the same request methods without sharing a trait, so both requests
+// are created by this macro. It adds the headers, body, HTTP version and Authorization header.
+macro_rules! build_request {
+  ($client:expr, $request:expr, $auth:expr) => {{
+    let request: &PreparedRequest = $request;
+    let auth: &Auth = $auth;
+    let mut builder = $client.request(request.method.clone(), request.url.as_str());
+
+    for (name, value) in request.headers.iter() {
+      builder = builder.header(name.as_str(), value.as_str());
+    }
+
+    if let Some(body) = request.body.as_ref() {
+      builder = builder.body(body.clone());
+    }
+
+    if let Some(version) = request.version {
+      builder = builder.version(version);
+    }
+
+    if let Some(h) = auth.header(request.method.as_str(), request.url.as_str()) {
+      builder = builder.header(AUTHORIZATION, h);
+    }
+
+    builder
+  }};
+}
+
+impl PreparedRequest {
+
+  /**Function adds the cookies that match the url to the Cookie header, merging them with a Cookie header
+   * from the request template.
+   * Params:
+   *  &mut self
+   *  cookies: &Cookies {The cookies loaded for the scan.}
+   * Returns nothing.
+   */
+  pub fn add_cookies(&mut self, cookies: &Cookies) {
+    let pos = self.headers.iter().position(|(n, _)| n.eq_ignore_ascii_case(COOKIE.as_str()));
+    let current = pos.map(|i| self.headers[i].1.as_str());
+
+    match (cookies.merge_header(self.url.as_str(), current), pos) {
+      (Some(merged), Some(i)) => { self.headers[i].1 = merged; },
+      (Some(merged), None) => { self.headers.push((COOKIE.as_str().to_owned(), merged)); },
+      (None, _) => {}
+    }
+  }
+
+  /**Function sends the request and answers a new digest challenge by sending it again.
+   * Params:
+   *  &self
+   *  client: &Client {The http client.}
+   *  auth:   &Auth   {The authentication added to the request.}
+   * Returns Result<Response, Error>
+   */
+  pub fn send(&self, client: &Client, auth: &Auth) -> Result<Response, Error> {
+    let response = build_request!(client, self, auth).send()?;
+
+    match auth.new_challenge(response.status(), response.headers()) {
+      true => build_request!(client, self, auth).send(),
+      false => Ok(response),
+    }
+  }
+
+  /**Function sends the request from the async engine and answers a new digest challenge by sending it again.
+   * Params:
+   *  &self
+   *  client: &reqwest::Client {The async http client.}
+   *  auth:   &Auth            {The authentication added to the request.}
+   * Returns Result<reqwest::Response, Error>
+   */
+  pub async fn send_async(&self, client: &reqwest::Client, auth: &Auth) -> Result<reqwest::Response, Error> {
+    let response = build_request!(client, self, auth).send().await?;
+
+    match auth.new_challenge(response.status(), response.headers()) {
+      true => build_request!(client, self, auth).send().await,
+      false => Ok(response),
+    }
+  }
+}
+
+/**Function returns where a redirect points to.
+ * Params:
+ *  status:  StatusCode  {The status code of the response.}
+ *  headers: &HeaderMap  {The response headers.}
+ * Returns Option<String> {None when the response is not a redirect.}
+ */
+pub fn redirect_location(status: StatusCode, headers: &HeaderMap) -> Option<String> {
+  match status.is_redirection() {
+    true => headers.get(LOCATION).and_then(|l| l.to_str().ok()).map(|l| l.to_owned()),
+    false => None,
+  }
+}
+
+impl RawRequest {
+
+  /**Function parses a raw HTTP/1.x request such as one saved from Burp.
+   * Params:
+   *  text: &str {The request line, headers, a blank line and the body.}
+   * Returns Result<RawRequest, String>
+   */
+  pub fn parse(text: &str) -> Result<RawRequest, String> {
+    let text = text.trim_start_matches(['\r', '\n']);
+    let (head, body) = match text.find("\r\n\r\n") {
+      Some(i) => (&text[..i], &text[i+4..]),
+      None => {
+        match text.find("\n\n") {
+          Some(i) => (&text[..i], &text[i+2..]),
+          None => (text, ""),
+        }
+      }
+    };
+
+    let mut lines = head.lines().map(|l| l.trim_end_matches('\r'));
+
+    let request_line = match lines.next() {
+      Some(l) => l,
+      None => { return Err("the request is empty".to_owned()); }
+    };
+
+    let parts: Vec<&str> = request_line.split_whitespace().collect();
+    if parts.len() < 2 || parts.len() > 3 {
+      return Err(format!("invalid request line {} - expected METHOD PATH HTTP/1.1", request_line));
+    }
+
+    let mut headers: Vec<(String, String)> = Default::default();
+    for line in lines {
+      let (name, value) = parse_header(line)?;
+
+      if !DROPPED_RAW_HEADERS.contains(&name.to_lowercase().as_str()) {
+        headers.push((name, value));
+      }
+    }
+
+    Ok(RawRequest {
+      method: parts[0].to_owned(),
+      target: parts[1].to_owned(),
+      headers,
+      body: match body.len() { 0 => None, _ => Some(body.to_owned()) },
+    })
+  }
+
+  /**Function returns the value of a header. */
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+  }
+
+  /**Function checks if the base url has a host, which replaces the host the request was sent to. */
+  pub fn overrides_host(base: &str) -> bool {
+    match base.split_once("://") {
+      Some((_, rest)) => { !rest.split(['/', '?']).next().unwrap_or("").is_empty() },
+      None => false,
+    }
+  }
+
+  /**Function builds the url of the request. A base url with a host replaces the scheme and host of the
+   * request, so captured requests can be pointed at another server. Otherwise absolute urls are used as
+   * they are and paths are sent to the Host header using the scheme of the base url, e.g. https://
+   * Params:
+   *  &self
+   *  base: &str {The url from the command line.}
+   * Returns Result<String, String>
+   */
+  pub fn url(&self, base: &str) -> Result<String, String> {
+    let (base_scheme, base_rest) = match base.split_once("://") {
+      Some(s) => s,
+      None => { return Err(format!("{} does not have a scheme, e.g. https://", base)); }
+    };
+
+    let base_authority = base_rest.split(['/', '?']).next().unwrap_or("");
+
+    // Absolute urls are used by requests sent to a proxy and by curl and HAR imports.
+    let (target_origin, path) = match self.target.split_once("://") {
+      Some((scheme, rest)) if scheme == "http" || scheme == "https" => {
+        let split = rest.find(['/', '?']).unwrap_or(rest.len());
+        (Some((scheme, &rest[..split])), rest[split..].to_owned())
+      },
+      _ => (None, self.target.clone()),
+    };
+
+    let (scheme, authority) = match (base_authority, target_origin) {
+      ("", Some(origin)) => origin,
+      ("", None) => {
+        match self.header("Host") {
+          Some(h) => (base_scheme, h),
+          None => { return Err("the request does not have a Host header, add the host to the url".to_owned()); }
+        }
+      },
+      (a, _) => (base_scheme, a),
+    };
+
+    let path = match path.starts_with('/') {
+      true => path,
+      false => format!("/{}", path),
+    };
+
+    Ok(format!("{}://{}{}", scheme, authority, path))
+  }
+}
@@ -0,0 +1,381 @@
+// Module implements a subset of the hashcat/john rule language for expanding each word into variants.
+// A rules file has one rule per line, each rule is a sequence of functions applied from left to right.
+//
+//   :     do nothing                 l     lowercase                  u     uppercase
+//   c     capitalize                 C     invert capitalize          t     toggle case
+//   TN    toggle case at N           E     title case                 r     reverse
+//   d     duplicate                  pN    append N copies            f     reflect
+//   {     rotate left                }     rotate right               $X    append X
+//   ^X    prepend X                  [     delete first               ]     delete last
+//   DN    delete at N                xNM   extract M from N           iNX   insert X at N
+//   oNX   overwrite N with X         'N    truncate at N              sXY   replace X with Y
+//   @X    purge X                    zN    duplicate first N times    ZN    duplicate last N times
+//   q     duplicate every char
+//
+// Positions are 0-9 followed by A-Z for 10-35. Lines starting with # are comments.
+use std::{
+  fs,
+  collections::HashSet,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuleOp {
+  Noop,
+  Lower,
+  Upper,
+  Capitalize,
+  InvertCapitalize,
+  ToggleAll,
+  ToggleAt(usize),
+  TitleCase,
+  Reverse,
+  Duplicate,
+  DuplicateN(usize),
+  Reflect,
+  RotateLeft,
+  RotateRight,
+  Append(char),
+  Prepend(char),
+  DeleteFirst,
+  DeleteLast,
+  DeleteAt(usize),
+  Extract(usize, usize),
+  Insert(usize, char),
+  Overwrite(usize, char),
+  Truncate(usize),
+  Replace(char, char),
+  Purge(char),
+  DupFirst(usize),
+  DupLast(usize),
+  DupChars,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+  pub ops: Vec<RuleOp>,
+  pub source: String,       // The rule as it was written in the rules file.
+}
+
+/**Function converts a hashcat position character into a number. */
+fn position(ch: Option<char>) -> Result<usize, String> {
+  match ch {
+    Some(c @ '0'..='9') => { Ok(c as usize - '0' as usize) },
+    Some(c @ 'A'..='Z') => { Ok(c as usize - 'A' as usize + 10) },
+    Some(c) => { Err(format!("invalid position {}", c)) },
+    None => { Err("missing position".to_owned()) }
+  }
+}
+
+fn argument(ch: Option<char>) -> Result<char, String> {
+  ch.ok_or_else(|| "missing character".to_owned())
+}
+
+fn toggle(c: char) -> char {
+  if c.is_uppercase() {
+    c.to_lowercase().next().unwrap_or(c)
+  }
+  else {
+    c.to_uppercase().next().unwrap_or(c)
+  }
+}
+
+impl Rule {
+
+  /**Function parses a single rule.
+   * Params:
+   *  line: &str {The rule, e.g. c $2 $0 $2 $4}
+   * Returns Result<Rule, String>
+   */
+  pub fn parse(line: &str) -> Result<Rule, String> {
+    let mut ops: Vec<RuleOp> = Default::default();
+    let mut chars = line.chars();
+
+    while let Some(f) = chars.next() {
+      let op = match f {
+        ' ' | '\t' => { continue; },
+        ':' => RuleOp::Noop,
+        'l' => RuleOp::Lower,
+        'u' => RuleOp::Upper,
+        'c' => RuleOp::Capitalize,
+        'C' => RuleOp::InvertCapitalize,
+        't' => RuleOp::ToggleAll,
+        'T' => RuleOp::ToggleAt(position(chars.next())?),
+        'E' => RuleOp::TitleCase,
+        'r' => RuleOp::Reverse,
+        'd' => RuleOp::Duplicate,
+        'p' => RuleOp::DuplicateN(position(chars.next())?),
+        'f' => RuleOp::Reflect,
+        '{' => RuleOp::RotateLeft,
+        '}' => RuleOp::RotateRight,
+        '$' => RuleOp::Append(argument(chars.next())?),
+        '^' => RuleOp::Prepend(argument(chars.next())?),
+        '[' => RuleOp::DeleteFirst,
+        ']' => RuleOp::DeleteLast,
+        'D' => RuleOp::DeleteAt(position(chars.next())?),
+        'x' => RuleOp::Extract(position(chars.next())?, position(chars.next())?),
+        'i' => RuleOp::Insert(position(chars.next())?, argument(chars.next())?),
+        'o' => RuleOp::Overwrite(position(chars.next())?, argument(chars.next())?),
+        '\'' => RuleOp::Truncate(position(chars.next())?),
+        's' => RuleOp::Replace(argument(chars.next())?, argument(chars.next())?),
+        '@' => RuleOp::Purge(argument(chars.next())?),
+        'z' => RuleOp::DupFirst(position(chars.next())?),
+        'Z' => RuleOp::DupLast(position(chars.next())?),
+        'q' => RuleOp::DupChars,
+        _ => { return Err(format!("unknown rule function {}", f)); }
+      };
+
+      ops.push(op);
+    }
+
+    if ops.is_empty() {
+      return Err("empty rule".to_owned());
+    }
+
+    Ok(Rule { ops, source: line.to_owned() })
+  }
+
+  /**Function applies every function in the rule to a word.
+   * Params:
+   *  &self
+   *  word: &str {The word from the wordlist.}
+   * Returns String.
+   */
+  pub fn apply(&self, word: &str) -> String {
+    let mut w: Vec<char> = word.chars().collect();
+
+    for op in self.ops.iter() {
+      match *op {
+        RuleOp::Noop => {},
+        RuleOp::Lower => { w = w.iter().flat_map(|c| c.to_lowercase()).collect(); },
+        RuleOp::Upper => { w = w.iter().flat_map(|c| c.to_uppercase()).collect(); },
+        RuleOp::Capitalize => {
+          w = w.iter().flat_map(|c| c.to_lowercase()).collect();
+          if let Some(first) = w.first_mut() { *first = first.to_uppercase().next().unwrap_or(*first); }
+        },
+        RuleOp::InvertCapitalize => {
+          w = w.iter().flat_map(|c| c.to_uppercase()).collect();
+          if let Some(first) = w.first_mut() { *first = first.to_lowercase().next().unwrap_or(*first); }
+        },
+        RuleOp::ToggleAll => { w = w.iter().map(|c| toggle(*c)).collect(); },
+        RuleOp::ToggleAt(n) => {
+          if let Some(c) = w.get_mut(n) { *c = toggle(*c); }
+        },
+        RuleOp::TitleCase => {
+          let mut start = true;
+          for c in w.iter_mut() {
+            *c = match start {
+              true => c.to_uppercase().next().unwrap_or(*c),
+              false => c.to_lowercase().next().unwrap_or(*c),
+            };
+            start = *c == ' ';
+          }
+        },
+        RuleOp::Reverse => { w.reverse(); },
+        RuleOp::Duplicate => { w.extend(w.clone()); },
+        RuleOp::DuplicateN(n) => {
+          let copy = w.clone();
+          for _ in 0..n { w.extend(copy.iter()); }
+        },
+        RuleOp::Reflect => {
+          let copy: Vec<char> = w.iter().rev().copied().collect();
+          w.extend(copy);
+        },
+        RuleOp::RotateLeft => {
+          if !w.is_empty() { w.rotate_left(1); }
+        },
+        RuleOp::RotateRight => {
+          if !w.is_empty() { w.rotate_right(1); }
+        },
+        RuleOp::Append(c) => { w.push(c); },
+        RuleOp::Prepend(c) => { w.insert(0, c); },
+        RuleOp::DeleteFirst => {
+          if !w.is_empty() { w.remove(0); }
+        },
+        RuleOp::DeleteLast => { w.pop(); },
+        RuleOp::DeleteAt(n) => {
+          if n < w.len() { w.remove(n); }
+        },
+        RuleOp::Extract(n, m) => {
+          if n + m <= w.len() { w = w[n..n+m].to_vec(); }        // Like hashcat, the word is kept when the range is too long.
+        },
+        RuleOp::Insert(n, c) => {
+          if n <= w.len() { w.insert(n, c); }
+        },
+        RuleOp::Overwrite(n, c) => {
+          if let Some(x) = w.get_mut(n) { *x = c; }
+        },
+        RuleOp::Truncate(n) => { w.truncate(n); },
+        RuleOp::Replace(from, to) => {
+          for c in w.iter_mut() {
+            if *c == from { *c = to; }
+          }
+        },
+        RuleOp::Purge(p) => { w.retain(|c| *c != p); },
+        RuleOp::DupFirst(n) => {
+          if let Some(first) = w.first().copied() {
+            for _ in 0..n { w.insert(0, first); }
+          }
+        },
+        RuleOp::DupLast(n) => {
+          if let Some(last) = w.last().copied() {
+            for _ in 0..n { w.push(last); }
+          }
+        },
+        RuleOp::DupChars => {
+          w = w.iter().flat_map(|c| [*c, *c]).collect();
+        }
+      }
+    }
+
+    w.into_iter().collect()
+  }
+}
+
+/**Function reads a rules file, one rule per line. Blank lines and lines starting with # are skipped.
+ * Params:
+ *  file_name: &str {The path to the rules file.}
+ * Returns Result<Vec<Rule>, String>
+ */
+pub fn load_rules(file_name: &str) -> Result<Vec<Rule>, String> {
+  let contents = match fs::read_to_string(file_name) {
+    Ok(c) => c,
+    Err(e) => { return Err(format!("unable to read rules file {} - {}", file_name, e)); }
+  };
+
+  let mut rules: Vec<Rule> = Default::default();
+
+  for (i, line) in contents.lines().enumerate() {
+    let line = line.trim_end_matches('\r');
+
+    if line.trim().is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    match Rule::parse(line) {
+      Ok(rule) => { rules.push(rule); },
+      Err(e) => { return Err(format!("{} line {}: {}", file_name, i+1, e)); }
+    }
+  }
+
+  if rules.is_empty() {
+    return Err(format!("{} does not contain any rules", file_name));
+  }
+
+  Ok(rules)
+}
+
+/**Function applies every rule to a word and returns the unique, non-empty variants in rule order.
+ * Params:
+ *  rules: &[Rule] {The rules loaded from the rules file.}
+ *  word:  &str    {The word from the wordlist.}
+ * Returns Vec<String>
+ */
+pub fn expand(rules: &[Rule], word: &str) -> Vec<String> {
+  let mut seen: HashSet<String> = Default::default();
+  let mut out: Vec<String> = Default::default();
+
+  for rule in rules {
+    let variant = rule.apply(word);
+
+    if !variant.is_empty() && seen.insert(variant.clone()) {
+      out.push(variant);
+    }
+  }
+
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::arguments::test_files::temp_file;
+
+  /**Function applies a single rule to the example word used in the hashcat documentation. */
+  fn apply(rule: &str) -> String {
+    Rule::parse(rule).unwrap().apply("p@ssW0rd")
+  }
+
+  #[test]
+  fn case_rules_match_hashcat() {
+    assert_eq!(apply(":"), "p@ssW0rd");
+    assert_eq!(apply("l"), "p@ssw0rd");
+    assert_eq!(apply("u"), "P@SSW0RD");
+    assert_eq!(apply("c"), "P@ssw0rd");
+    assert_eq!(apply("C"), "p@SSW0RD");
+    assert_eq!(apply("t"), "P@SSw0RD");
+    assert_eq!(apply("T3"), "p@sSW0rd");
+    assert_eq!(Rule::parse("E").unwrap().apply("p@ssW0rd w0rld"), "P@ssw0rd W0rld");
+  }
+
+  #[test]
+  fn position_rules_match_hashcat() {
+    assert_eq!(apply("r"), "dr0Wss@p");
+    assert_eq!(apply("d"), "p@ssW0rdp@ssW0rd");
+    assert_eq!(apply("p2"), "p@ssW0rdp@ssW0rdp@ssW0rd");
+    assert_eq!(apply("f"), "p@ssW0rddr0Wss@p");
+    assert_eq!(apply("{"), "@ssW0rdp");
+    assert_eq!(apply("}"), "dp@ssW0r");
+    assert_eq!(apply("$1"), "p@ssW0rd1");
+    assert_eq!(apply("^1"), "1p@ssW0rd");
+    assert_eq!(apply("["), "@ssW0rd");
+    assert_eq!(apply("]"), "p@ssW0r");
+    assert_eq!(apply("D3"), "p@sW0rd");
+    assert_eq!(apply("x04"), "p@ss");
+    assert_eq!(apply("i4!"), "p@ss!W0rd");
+    assert_eq!(apply("o3$"), "p@s$W0rd");
+    assert_eq!(apply("'6"), "p@ssW0");
+  }
+
+  #[test]
+  fn character_rules_match_hashcat() {
+    assert_eq!(apply("ss$"), "p@$$W0rd");
+    assert_eq!(apply("@s"), "p@W0rd");
+    assert_eq!(apply("z2"), "ppp@ssW0rd");
+    assert_eq!(apply("Z2"), "p@ssW0rddd");
+    assert_eq!(apply("q"), "pp@@ssssWW00rrdd");
+    assert_eq!(apply("c $2 $0 $2 $4"), "P@ssw0rd2024");
+    assert_eq!(apply("sa@ $ $!"), "p@ssW0rd !");
+  }
+
+  #[test]
+  fn out_of_range_positions_keep_the_word() {
+    assert_eq!(apply("TZ"), "p@ssW0rd");
+    assert_eq!(apply("D9"), "p@ssW0rd");
+    assert_eq!(apply("o9x"), "p@ssW0rd");
+    assert_eq!(apply("i9x"), "p@ssW0rd");
+    assert_eq!(apply("i8x"), "p@ssW0rdx");
+    assert_eq!(apply("x65"), "p@ssW0rd");
+    assert_eq!(apply("'9"), "p@ssW0rd");
+    assert_eq!(Rule::parse("[ ] { }").unwrap().apply(""), "");
+  }
+
+  #[test]
+  fn bad_rules_are_errors() {
+    assert_eq!(Rule::parse("K"), Err("unknown rule function K".to_owned()));
+    assert_eq!(Rule::parse("Ta"), Err("invalid position a".to_owned()));
+    assert_eq!(Rule::parse("D"), Err("missing position".to_owned()));
+    assert_eq!(Rule::parse("c $"), Err("missing character".to_owned()));
+    assert_eq!(Rule::parse("s1"), Err("missing character".to_owned()));
+    assert_eq!(Rule::parse(" \t"), Err("empty rule".to_owned()));
+  }
+
+  #[test]
+  fn load_rules_skips_comments_and_reports_bad_lines() {
+    let file = temp_file("rules", "# best rules\r\n:\r\n\r\nc $1\n");
+    let rules = load_rules(file.as_str()).unwrap();
+    assert_eq!(rules.iter().map(|r| r.source.as_str()).collect::<Vec<&str>>(), vec![":", "c $1"]);
+
+    temp_file("rules", "u\nc $\n");
+    assert_eq!(load_rules(file.as_str()), Err(format!("{} line 2: missing character", file)));
+
+    temp_file("rules", "# nothing\n\n");
+    assert_eq!(load_rules(file.as_str()), Err(format!("{} does not contain any rules", file)));
+  }
+
+  #[test]
+  fn expand_drops_duplicate_and_empty_variants() {
+    let rules: Vec<Rule> = [":", "l", "u", "'0", "c"].iter().map(|r| Rule::parse(r).unwrap()).collect();
+
+    assert_eq!(expand(&rules, "admin"), vec!["admin", "ADMIN", "Admin"]);
+  }
+}
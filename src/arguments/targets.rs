@@ -0,0 +1,96 @@
+// Module loads the base urls scanned with --targets. Every target gets the path and query of the url from
+// the command line and the test cases of all targets are interleaved so the load is spread across hosts.
+use std::{
+  fs,
+  io::{self, Read},
+  sync::Arc,
+};
+
+use super::wordlist::STDIN_WORDLIST;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+  pub name: Arc<str>,       // The base url as it was listed, used to tag results.
+  pub url: String,          // The url fuzzed for this target.
+}
+
+/**Function reads the targets from a file or stdin, one base url per line. Blank lines, comments and
+ * duplicates are skipped.
+ * Params:
+ *  spec: &str {The file name, or - for stdin.}
+ * Returns Result<Vec<String>, String>
+ */
+pub fn load_targets(spec: &str) -> Result<Vec<String>, String> {
+  let text = match spec {
+    STDIN_WORDLIST => {
+      let mut text = String::new();
+      match io::stdin().read_to_string(&mut text) {
+        Ok(_) => text,
+        Err(e) => { return Err(format!("unable to read targets from stdin - {}", e)); }
+      }
+    },
+    _ => {
+      match fs::read_to_string(spec) {
+        Ok(t) => t,
+        Err(e) => { return Err(format!("unable to read targets file {} - {}", spec, e)); }
+      }
+    }
+  };
+
+  let mut targets: Vec<String> = Default::default();
+
+  for (i, line) in text.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    match line.split_once("://") {
+      Some((scheme, rest)) if (scheme == "http" || scheme == "https") && !rest.is_empty() => {},
+      _ => { return Err(format!("{} line {}: {} must start with http:// or https://", spec, i+1, line)); }
+    }
+
+    if !targets.iter().any(|t| t == line) {
+      targets.push(line.to_owned());
+    }
+  }
+
+  match targets.len() {
+    0 => Err(format!("{} does not contain any targets", spec)),
+    _ => Ok(targets),
+  }
+}
+
+/**Function returns the path and query of a url, e.g. /api?id={!} for http://host/api?id={!}
+ * Params:
+ *  url: &str {The url from the command line.}
+ * Returns &str {Empty when the url only has a scheme and host.}
+ */
+pub fn url_path(url: &str) -> &str {
+  match url.split_once("://") {
+    Some((_, rest)) => {
+      match rest.find(['/', '?']) {
+        Some(i) => &rest[i..],
+        None => "",
+      }
+    },
+    None => url,
+  }
+}
+
+/**Function builds the url fuzzed for a target from the path and query of the url.
+ * Params:
+ *  target: &str {The base url of the target.}
+ *  url:    &str {The url from the command line.}
+ * Returns String.
+ */
+pub fn target_url(target: &str, url: &str) -> String {
+  let path = url_path(url);
+
+  match path.len() {
+    0 => target.to_owned(),
+    _ if path.starts_with('?') && !url_path(target).is_empty() => format!("{}{}", target, path),
+    _ if path.starts_with('?') => format!("{}/{}", target, path),
+    _ => format!("{}{}", target.trim_end_matches('/'), path),
+  }
+}
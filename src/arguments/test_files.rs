@@ -0,0 +1,14 @@
+// Module holds the helpers shared by the tests that read and write files.
+use std::fs;
+
+/**Function writes a file in the temp directory, named after the test process so parallel runs do not collide.
+ * Params:
+ *  name:     &str {A name unique among the tests.}
+ *  contents: &str {The contents of the file, empty for a file the test appends to.}
+ * Returns String {The path of the file.}
+ */
+pub fn temp_file(name: &str, contents: &str) -> String {
+  let path = std::env::temp_dir().join(format!("soda_{}_{}", std::process::id(), name));
+  fs::write(&path, contents).unwrap();
+  path.to_string_lossy().into_owned()
+}
@@ -0,0 +1,95 @@
+// Module checks the url before the scan starts. The {!} and {csrf} markers are swapped for a stand in while
+// the url is parsed because braces are not valid in every part of a url, then the url is checked for the
+// places a payload cannot go and given a trailing slash when words are appended to the path.
+use reqwest::Url;
+
+use super::{Fuzz, F_HTTP, F_HTTPS};
+use super::request::{FUZZ_MARKER, CSRF_MARKER};
+
+const MARKER_STAND_IN: &str = "soda-marker";      // Valid in a host name, path and query.
+
+/**Function parses a url that may contain the {!} and {csrf} markers.
+ * Params:
+ *  url: &str {The url.}
+ * Returns Result<Url, String> {The url with the markers replaced by a stand in.}
+ */
+pub fn parse_url(url: &str) -> Result<Url, String> {
+  let (scheme, rest) = match url.split_once("://") {
+    Some(s) => s,
+    None => { return Err(format!("{} does not have a scheme, the url must start with {} or {}", url, F_HTTP, F_HTTPS)); }
+  };
+
+  if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+    return Err(format!("unsupported scheme {} in {}, the url must start with {} or {}", scheme, url, F_HTTP, F_HTTPS));
+  }
+
+  let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+  let host_port = authority.rsplit('@').next().unwrap_or("");
+
+  if host_port.is_empty() {
+    return Err(format!("{} does not have a host", url));
+  }
+
+  // Ports are numbers, the closing bracket skips the colons of IPv6 addresses.
+  let port = match host_port.rfind(':') {
+    Some(i) if !host_port[i..].contains(']') => Some(&host_port[i+1..]),
+    _ => None,
+  };
+
+  if let Some(p) = port {
+    if p.contains(FUZZ_MARKER) || p.contains(CSRF_MARKER) {
+      return Err(format!("{} cannot be used in the port of {}", FUZZ_MARKER, url));
+    }
+
+    if !p.is_empty() && p.parse::<u16>().is_err() {
+      return Err(format!("invalid port {} in {}, ports are numbers from 0 to 65535", p, url));
+    }
+  }
+
+  let stand_in = url.replace(FUZZ_MARKER, MARKER_STAND_IN).replace(CSRF_MARKER, MARKER_STAND_IN);
+
+  let parsed = match Url::parse(stand_in.as_str()) {
+    Ok(u) => u,
+    Err(e) => { return Err(format!("invalid url {} - {}", url, e)); }
+  };
+
+  // The fragment stays in the browser and is never sent to the server.
+  if parsed.fragment().map(|f| f.contains(MARKER_STAND_IN)).unwrap_or(false) {
+    return Err(format!("{} is after the # in {}, the fragment is never sent to the server", FUZZ_MARKER, url));
+  }
+
+  Ok(parsed)
+}
+
+/**Function checks the url for the fuzzing mode and adds the trailing slash directory paths need.
+ * Params:
+ *  url:            &str  {The url from the command line.}
+ *  fuzz:           Fuzz  {The fuzzing mode.}
+ *  fuzzes_request: bool  {True when {!} is in the headers or body.}
+ * Returns Result<String, String> {The url used for the scan.}
+ */
+pub fn normalize_url(url: &str, fuzz: Fuzz, fuzzes_request: bool) -> Result<String, String> {
+  let url = url.trim();
+  let parsed = parse_url(url)?;
+  let mut url = url.to_owned();
+
+  match fuzz {
+    Fuzz::DirectoryPath if !url.contains(FUZZ_MARKER) => {
+      if parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err(format!(
+          "directory-path fuzzing appends each word to the end of {}, put {} in the path to fuzz before the query", url, FUZZ_MARKER
+        ));
+      }
+
+      if !url.ends_with('/') {
+        url.push('/');
+      }
+    },
+    Fuzz::Parameter if !url.contains(FUZZ_MARKER) && !fuzzes_request => {
+      return Err(format!("parameter fuzzing needs {} in the url, a header or the body, e.g. {}?id={}", FUZZ_MARKER, url, FUZZ_MARKER));
+    },
+    _ => {}
+  }
+
+  Ok(url)
+}
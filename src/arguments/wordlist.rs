@@ -0,0 +1,330 @@
+// Module streams words from one or more wordlists, a directory of wordlists, payload generators or stdin.
+// Compressed wordlists are detected by their magic bytes and decompressed while they are read.
+use std::{
+  io::{self, BufRead, BufReader, Read, Error as IoError, ErrorKind},
+  fs::{self, File},
+  collections::{HashSet, VecDeque},
+};
+
+use flate2::bufread::MultiGzDecoder;
+use bzip2::bufread::MultiBzDecoder;
+use xz2::bufread::XzDecoder;
+
+use super::arg_fmt;
+use super::normalizer::{self, NormalizeOptions, DropStats, Line};
+use super::generators::{Generator, GeneratorReader};
+
+pub const STDIN_WORDLIST: &str = "-";     // Reads the wordlist from stdin.
+pub const WORDLIST_DELIMITER: &str = ",";  // Separates multiple wordlist paths.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+  None,
+  Gzip,
+  Bzip2,
+  Xz,
+  Zstd,
+}
+
+impl Compression {
+
+  /**Function detects the compression format from the first bytes of a file.
+   * Params:
+   *  magic: &[u8] {The start of the file.}
+   * Returns Compression.
+   */
+  pub fn detect(magic: &[u8]) -> Compression {
+    if magic.starts_with(&[0x1F, 0x8B]) {
+      Compression::Gzip
+    }
+    else if magic.starts_with(b"BZh") {
+      Compression::Bzip2
+    }
+    else if magic.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+      Compression::Xz
+    }
+    else if magic.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+      Compression::Zstd
+    }
+    else {
+      Compression::None
+    }
+  }
+
+  /**Function wraps a reader with the decoder for the compression format.
+   * Params:
+   *  &self
+   *  reader: Box<dyn BufRead> {The raw reader.}
+   * Returns Result<Box<dyn BufRead>, IoError>
+   */
+  pub fn decoder(&self, reader: Box<dyn BufRead>) -> Result<Box<dyn BufRead>, IoError> {
+    match self {
+      Compression::None => { Ok(reader) },
+      Compression::Gzip => { Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader)))) },
+      Compression::Bzip2 => { Ok(Box::new(BufReader::new(MultiBzDecoder::new(reader)))) },
+      Compression::Xz => { Ok(Box::new(BufReader::new(XzDecoder::new_multi_decoder(reader)))) },
+      Compression::Zstd => { Ok(Box::new(BufReader::new(zstd::stream::read::Decoder::with_buffer(reader)?))) }
+    }
+  }
+}
+
+/**Function checks the magic bytes at the start of a reader without consuming them and returns a reader
+ * that produces the decompressed contents.
+ * Params:
+ *  reader: Box<dyn BufRead> {The raw reader.}
+ * Returns Result<Box<dyn BufRead>, IoError>
+ */
+pub fn decompress(mut reader: Box<dyn BufRead>) -> Result<Box<dyn BufRead>, IoError> {
+  let compression = Compression::detect(reader.fill_buf()?);
+  compression.decoder(reader)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WordlistSource {
+  Stdin,
+  File(String),
+  Generator(String, Generator),     // The argument the generator was parsed from and the generator.
+  Checkpoint(String),               // The words left in a checkpoint file, used as they were saved.
+}
+
+impl WordlistSource {
+  pub fn name(&self) -> &str {
+    match self {
+      WordlistSource::Stdin => { STDIN_WORDLIST },
+      WordlistSource::File(path) => { path.as_str() },
+      WordlistSource::Generator(spec, _) => { spec.as_str() },
+      WordlistSource::Checkpoint(path) => { path.as_str() }
+    }
+  }
+
+  /**Function checks if the words are used as they are, without trimming, comments, blank lines or duplicates.
+   * Generated words and the words saved in a checkpoint are never filtered. */
+  pub fn is_raw(&self) -> bool {
+    matches!(self, WordlistSource::Generator(_, _) | WordlistSource::Checkpoint(_))
+  }
+
+  /**Function opens the source for reading, decompressing gzip, bzip2, xz and zstd wordlists on the fly.
+   * Byte order marks are removed and UTF-16 wordlists are converted to UTF-8. Generators and checkpoints are
+   * already UTF-8 and are read as they are.
+   * Params:
+   *  &self
+   * Returns Result<Box<dyn BufRead>, IoError>
+   */
+  pub fn open(&self) -> Result<Box<dyn BufRead>, IoError> {
+    let reader: Box<dyn BufRead> = match self {
+      WordlistSource::Stdin => { decompress(Box::new(BufReader::new(io::stdin())))? },
+      WordlistSource::File(path) => { decompress(Box::new(BufReader::new(File::open(path)?)))? },
+      WordlistSource::Generator(_, generator) => { return Ok(Box::new(BufReader::new(GeneratorReader::new(generator)))); },
+      WordlistSource::Checkpoint(path) => { return Ok(Box::new(BufReader::new(File::open(path)?))); }
+    };
+
+    normalizer::decode_text(reader)
+  }
+
+  /**Function returns the size of the source on disk, before decompression. Stdin and generators have no known size.
+   * Params:
+   *  &self
+   * Returns usize.
+   */
+  pub fn size(&self) -> usize {
+    match self {
+      WordlistSource::Stdin | WordlistSource::Generator(_, _) => { 0 },
+      WordlistSource::File(path) | WordlistSource::Checkpoint(path) => {
+        match fs::metadata(path) {
+          Ok(m) => { m.len() as usize },
+          Err(_) => { 0 }
+        }
+      }
+    }
+  }
+
+  /**Function reads up to max bytes from the start of the source without consuming stdin.
+   * Params:
+   *  &self
+   *  max: usize {The number of bytes to read.}
+   * Returns Result<Vec<u8>, IoError>
+   */
+  pub fn peek(&self, max: usize) -> Result<Vec<u8>, IoError> {
+    let mut bytes = vec![];
+
+    match self {
+      WordlistSource::Stdin => {},
+      _ => {
+        self.open()?.take(max as u64).read_to_end(&mut bytes)?;
+      }
+    }
+
+    Ok(bytes)
+  }
+}
+
+/**Function turns a comma separated list of wordlist paths into sources. Directories are expanded
+ * into the files they contain, sorted by name, - reads from stdin and generators such as range:1-100 are parsed.
+ * Params:
+ *  spec: &str {The wordlist argument.}
+ * Returns Result<Vec<WordlistSource>, IoError>
+ */
+pub fn parse_sources(spec: &str) -> Result<Vec<WordlistSource>, IoError> {
+  let mut sources: Vec<WordlistSource> = Default::default();
+
+  for path in spec.split(WORDLIST_DELIMITER).map(|p| p.trim()).filter(|p| !p.is_empty()) {
+    if path == STDIN_WORDLIST {
+      if !sources.contains(&WordlistSource::Stdin) {
+        sources.push(WordlistSource::Stdin);
+      }
+
+      continue;
+    }
+
+    match Generator::parse(path) {
+      Ok(Some(generator)) => {
+        sources.push(WordlistSource::Generator(path.to_owned(), generator));
+        continue;
+      },
+      Ok(None) => {},
+      Err(e) => { return Err(IoError::new(ErrorKind::InvalidInput, e)); }
+    }
+
+    let metadata = match fs::metadata(path) {
+      Ok(m) => m,
+      Err(_) => {
+        return Err(IoError::new(ErrorKind::NotFound, format!("{} does not exist", path)));
+      }
+    };
+
+    if metadata.is_dir() {
+      let mut files: Vec<String> = Default::default();
+
+      for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+          files.push(entry.path().to_string_lossy().into_owned());
+        }
+      }
+
+      files.sort();
+      sources.extend(files.into_iter().map(WordlistSource::File));
+    }
+
+    else {
+      sources.push(WordlistSource::File(path.to_owned()));
+    }
+  }
+
+  if sources.is_empty() {
+    return Err(IoError::new(ErrorKind::InvalidInput, "no wordlist was supplied"));
+  }
+
+  Ok(sources)
+}
+
+/**Struct reads every source one after the other and yields the normalized words. Both the windows (\r\n)
+ * and the linux (\n) new line are accepted, even when they are mixed within or between files. */
+pub struct WordlistReader {
+  sources: VecDeque<WordlistSource>,
+  current: Option<Box<dyn BufRead>>,
+  current_raw: bool,          // The words of the current source are not normalized.
+  seen: HashSet<String>,
+  line: Vec<u8>,
+  pub options: NormalizeOptions,
+  pub debug: bool,
+  pub stats: DropStats,       // The number of blank lines, comments and duplicate words that were dropped.
+}
+
+impl WordlistReader {
+
+  /**Function creates a reader over the sources.
+   * Params:
+   *  sources: Vec<WordlistSource> {The wordlists to be read in order.}
+   *  options: NormalizeOptions    {Controls trimming, comments and duplicate words.}
+   *  debug:   bool                {Displays errors for sources that cannot be opened.}
+   * Returns WordlistReader.
+   */
+  pub fn new(sources: Vec<WordlistSource>, options: NormalizeOptions, debug: bool) -> WordlistReader {
+    WordlistReader {
+      sources: sources.into_iter().collect(),
+      current: None,
+      current_raw: false,
+      seen: Default::default(),
+      line: Default::default(),
+      options,
+      debug,
+      stats: Default::default(),
+    }
+  }
+
+  /**Function reads the next line from the current source, moving on to the next source at the end of each one.
+   * Params:
+   *  &mut self
+   * Returns Option<String>
+   */
+  fn next_line(&mut self) -> Option<String> {
+    loop {
+      if self.current.is_none() {
+        let source = self.sources.pop_front()?;
+
+        match source.open() {
+          Ok(reader) => {
+            self.current = Some(reader);
+            self.current_raw = source.is_raw();
+          },
+          Err(e) => {
+            arg_fmt::f_error("Unable to read wordlist", source.name(), format!("{}", e.kind()));
+            continue;
+          }
+        }
+      }
+
+      self.line.clear();
+      let reader = self.current.as_mut()?;
+
+      match reader.read_until(b'\n', &mut self.line) {
+        Ok(0) => { self.current = None; },
+        Ok(_) => {
+          if self.line.ends_with(b"\n") { self.line.pop(); }
+          if self.line.ends_with(b"\r") { self.line.pop(); }
+
+          return Some(String::from_utf8_lossy(&self.line).into_owned());
+        },
+        Err(e) => {
+          if self.debug {
+            println!("{}", e.kind());
+          }
+
+          self.current = None;
+        }
+      }
+    }
+  }
+}
+
+impl Iterator for WordlistReader {
+  type Item = String;
+
+  fn next(&mut self) -> Option<String> {
+    loop {
+      let line = self.next_line()?;
+
+      if self.current_raw {
+        return Some(line);
+      }
+
+      let word = match normalizer::classify(line.as_str(), &self.options) {
+        Line::Word(w) => w,
+        Line::Blank => { self.stats.blank += 1; continue; },
+        Line::Comment => { self.stats.comments += 1; continue; }
+      };
+
+      if self.options.dedup {
+        if self.seen.contains(word) {
+          self.stats.duplicates += 1;
+          continue;
+        }
+
+        self.seen.insert(word.to_owned());
+      }
+
+      return Some(word.to_owned());
+    }
+  }
+}
@@ -1,68 +1,89 @@
-#[path = "arguments\\mod.rs"]
+#[path = "arguments/mod.rs"]
 pub mod arguments;
 use crate::arguments::*;
 use arguments::display_help;
-use clap::Parser;
 
-fn main() -> () {
+fn main() {
   let env_arg: Vec<String> = std::env::args().collect();
-  match env_arg[1].as_str() {
-    "--help" | "-h" => {
+  match env_arg.get(1).map(|a| a.as_str()) {
+    Some("--help") | Some("-h") => {
       display_help();
     }
     _ => {}
   }
 
-  // Here we parse all the command line arguments.
-  let mut args = SodaArgs::parse();
+  // Here we parse all the command line arguments and fill in the rest from the config files.
+  let mut args = match SodaArgs::load() {
+    Ok(a) => a,
+    Err(e) => {
+      println!("Error: {}", e);
+      return;
+    }
+  };
+
+  if args.no_color {
+    console::set_colors_enabled(false);
+  }
 
-  if args.debug == true {
+  if args.debug {
     args.show_information();      // Shows the contents of the SodaArgs structure,
     args.dbg_print_chunk();       // Displays how the file is being parsed.
   }
 
-  // Returns if urls dont start with http(s)://
-  if !args.url.contains(F_HTTP) || args.url.contains(F_HTTPS) {
-    println!("url must start with {} or {}", F_HTTP, F_HTTPS);
-    return;
+  // The raw request file replaces the url, method, headers and body.
+  match args.load_request() {
+    Ok(_) => {},
+    Err(e) => {
+      println!("Error: {}", e);
+      return;
+    }
   }
 
-  // Adds a slash to the end of urls where not present.
-  else {
-    if args.fuzz == Fuzz::DirectoryPath && args.url.as_str().chars().last().unwrap() != '/' {
-      args.url.push('/');
+  // Returns if the url is not a valid http(s) url and adds the trailing slash to directory paths.
+  match args.check_url() {
+    Ok(_) => {},
+    Err(e) => {
+      println!("Error: {}", e);
+      return;
+    }
+  }
+
+  // Returns if --no-bare leaves no file name to request for each word.
+  match args.extension_plan().check() {
+    Ok(_) => {},
+    Err(e) => {
+      println!("Error: {}", e);
+      return;
     }
   }
 
   // Creates if the output file is not already created.
   match args.output.clone() {
-    Some(str) => {
-      if SodaArgs::file_exists(str.as_str()) == false {
-        
-        match SodaArgs::create_file(str.as_str()) {
-          Ok(_) => {
-            if args.verbose.clone() == true {
-              println!("Successfully created output file at {}", str.as_str());
-            }
-          },
+    Some(str) if !SodaArgs::file_exists(str.as_str()) => {
+      match SodaArgs::create_file(str.as_str()) {
+        Ok(_) => {
+          if args.verbose {
+            println!("Successfully created output file at {}", str.as_str());
+          }
+        },
 
-          Err(e) => {
-            if args.debug.clone() == true {
-              println!("Unable to create output file at {} with error: {}", str.as_str(), e.kind());
-            }
+        Err(e) => {
+          if args.debug {
+            println!("Unable to create output file at {} with error: {}", str.as_str(), e.kind());
           }
         }
       }
     },
-    None => {}
+    _ => {}
   }
 
-  // Checks if the wordlist exists and returns if not.
-  let check_file = SodaArgs::file_exists(args.wordlist.as_str());
-
-  if check_file == false {
-    println!("Error: {} does not exist", args.wordlist.as_str());
-    return;
+  // Checks if every wordlist exists and returns if not.
+  match args.wordlist_sources() {
+    Ok(_) => {},
+    Err(e) => {
+      println!("Error: {}", e);
+      return;
+    }
   }
 
   // Fuzzing starts here.